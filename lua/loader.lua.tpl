@@ -7,10 +7,12 @@ local localPlayer = Players.LocalPlayer
 local SERVER_URL    = "http://localhost:{{PORT}}"
 local INTERNAL_URL  = SERVER_URL .. "/internal"
 local HEALTH_URL    = SERVER_URL .. "/health"
+local HEARTBEAT_URL = SERVER_URL .. "/heartbeat"
 local SECRET        = "{{SECRET}}"
 local EXCHANGE_DIR  = "{{EXCHANGE_DIR}}"
 local PENDING_DIR   = EXCHANGE_DIR .. "/pending"
 local DONE_DIR      = EXCHANGE_DIR .. "/done"
+local RESULTS_DIR   = EXCHANGE_DIR .. "/results"
 local USERNAME      = localPlayer.Name
 local POLL_INTERVAL = 0.2
 local HEARTBEAT_INTERVAL = 5
@@ -60,6 +62,20 @@ local function sendLog(level, message, source)
     })
 end
 
+-- runtime error with its traceback, one stack frame per line
+local function sendError(message, traceback, source)
+    local stack = {}
+    for line in string.gmatch(traceback or "", "[^\n]+") do
+        table.insert(stack, line)
+    end
+    send({
+        event   = "error",
+        message = message,
+        source  = source or "roblox",
+        stack   = stack,
+    })
+end
+
 -- guard: already injected
 if getgenv and getgenv().__XENO_MCP_GENERIC_LOADER then
     sendEvent("already_attached")
@@ -103,8 +119,12 @@ if #missing > 0 then
     return
 end
 
+-- output lines of the script currently executing, nil when idle
+local capture = nil
+
 -- hook log output
 LogService.MessageOut:Connect(function(message, messageType)
+    if capture then table.insert(capture, message) end
     local level = "output"
     if messageType == Enum.MessageType.MessageWarning then
         level = "warn"
@@ -133,7 +153,14 @@ end)
 task.spawn(function()
     local serverLost = false
     while getgenv().__XENO_MCP_GENERIC_LOADER do
-        local ok = send({ event = "heartbeat" })
+        local ok = pcall(function()
+            request({
+                Url     = HEARTBEAT_URL,
+                Method  = "POST",
+                Headers = makeHeaders(),
+                Body    = HttpService:JSONEncode({ username = USERNAME })
+            })
+        end)
         if not ok and not serverLost then
             serverLost = true
             notify("Server connection lost — reconnecting...", 8)
@@ -152,6 +179,19 @@ end)
 -- script polling loop
 local VERIFY_URL = SERVER_URL .. "/verify-script"
 
+-- results are best-effort: executors without writefile still run scripts
+local function writeResult(fileId, status, output, returns, err)
+    if not fileId or type(writefile) ~= "function" then return end
+    pcall(writefile, RESULTS_DIR .. "/" .. fileId .. ".json", HttpService:JSONEncode({
+        file_id     = fileId,
+        status      = status,
+        output      = output or {},
+        returns     = returns or {},
+        error       = err,
+        finished_at = os.time(),
+    }))
+end
+
 while getgenv().__XENO_MCP_GENERIC_LOADER do
     local ok, files = pcall(listfiles, PENDING_DIR)
     if ok and files then
@@ -161,6 +201,7 @@ while getgenv().__XENO_MCP_GENERIC_LOADER do
                 if readOk and rawContent then
                     pcall(delfile, filePath)
 
+                    local fileId = string.match(filePath, "([^/\\]+)%.lua$")
                     local script = rawContent
                     local verified = true
 
@@ -194,6 +235,7 @@ while getgenv().__XENO_MCP_GENERIC_LOADER do
                         if not verified then
                             sendLog("error", "Rejected unsigned/invalid script: " .. filePath, "loader")
                             notify("Blocked unsigned script.", 5)
+                            writeResult(fileId, "error", nil, nil, "Rejected unsigned/invalid script")
                             script = nil
                         end
                     end
@@ -202,12 +244,29 @@ while getgenv().__XENO_MCP_GENERIC_LOADER do
                         sendLog("info", "Executing script: " .. filePath, "loader")
                         local fn, compileErr = loadstring(script)
                         if fn then
-                            local execOk, execErr = pcall(fn)
-                            if not execOk then
-                                sendLog("error", "Script error: " .. tostring(execErr), "loader")
+                            capture = {}
+                            local traceback
+                            local packed = table.pack(xpcall(fn, function(e)
+                                traceback = debug.traceback(tostring(e), 2)
+                                return e
+                            end))
+                            -- MessageOut may fire deferred; let it flush before collecting
+                            task.wait()
+                            local output = capture
+                            capture = nil
+                            if packed[1] then
+                                local returns = {}
+                                for i = 2, packed.n do
+                                    table.insert(returns, tostring(packed[i]))
+                                end
+                                writeResult(fileId, "done", output, returns, nil)
+                            else
+                                sendError("Script error: " .. tostring(packed[2]), traceback, "loader")
+                                writeResult(fileId, "error", output, nil, tostring(packed[2]))
                             end
                         else
                             sendLog("error", "Compile error: " .. tostring(compileErr), "loader")
+                            writeResult(fileId, "error", nil, nil, "Compile error: " .. tostring(compileErr))
                         end
                     end
                 end
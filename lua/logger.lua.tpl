@@ -77,7 +77,8 @@ if not serverOk then
 end
 
 -- hook log output
-LogService.MessageOut:Connect(function(message, messageType)
+local connections = {}
+connections[#connections + 1] = LogService.MessageOut:Connect(function(message, messageType)
     local level = "output"
     if messageType == Enum.MessageType.MessageWarning then
         level = "warn"
@@ -95,10 +96,24 @@ sendLog("info", "xeno-mcp logger attached", "xeno-mcp")
 notify("Logger connected and forwarding output.", 5)
 
 -- detect player leaving
-Players.PlayerRemoving:Connect(function(leavingPlayer)
+connections[#connections + 1] = Players.PlayerRemoving:Connect(function(leavingPlayer)
     if leavingPlayer == localPlayer then
         sendEvent("disconnected")
     end
 end)
 
+-- public API on getgenv() for POST /detach-logger
+if getgenv then
+    getgenv().__XENO_MCP_LOGGER = {
+        Disconnect = function()
+            for _, conn in ipairs(connections) do
+                pcall(function() conn:Disconnect() end)
+            end
+            connections = {}
+            getgenv().__XENO_MCP_LOGGER = nil
+            notify("Logger detached.", 4)
+        end,
+    }
+end
+
 print("[xeno-mcp] Logger hooked — forwarding output to " .. INTERNAL_URL)
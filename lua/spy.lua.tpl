@@ -5,6 +5,8 @@ local localPlayer = Players.LocalPlayer
 
 local SERVER_URL = "http://localhost:{{PORT}}/internal"
 local SECRET     = "{{SECRET}}"
+-- how many levels of nested tables to expand in logged args (per subscription overridable)
+local MAX_ARG_DEPTH = {{MAX_ARG_DEPTH}}
 local USERNAME   = localPlayer.Name
 
 local function makeHeaders()
@@ -35,31 +37,44 @@ local function notify(text, duration)
     end)
 end
 
--- format args to a readable string, capped at 200 chars
-local function formatArgs(args)
-    local parts = {}
-    for i, v in ipairs(args) do
-        local t = typeof(v)
-        if t == "string" then
-            local s = string.sub(v, 1, 40)
-            if #v > 40 then s = s .. "..." end
-            table.insert(parts, '"' .. s .. '"')
-        elseif t == "number" or t == "boolean" then
-            table.insert(parts, tostring(v))
-        elseif t == "Instance" then
-            table.insert(parts, t .. "<" .. v.ClassName .. ">")
-        elseif t == "EnumItem" then
-            table.insert(parts, tostring(v))
-        elseif t == "Vector3" or t == "CFrame" or t == "Color3" or t == "UDim2" then
-            table.insert(parts, tostring(v))
-        elseif t == "table" then
-            table.insert(parts, "table")
-        else
-            table.insert(parts, t)
+-- format one value; tables are expanded up to `depth` levels (20 entries each)
+local function formatValue(v, depth)
+    local t = typeof(v)
+    if t == "string" then
+        local s = string.sub(v, 1, 40)
+        if #v > 40 then s = s .. "..." end
+        return '"' .. s .. '"'
+    elseif t == "number" or t == "boolean" then
+        return tostring(v)
+    elseif t == "Instance" then
+        return t .. "<" .. v.ClassName .. ">"
+    elseif t == "EnumItem" then
+        return tostring(v)
+    elseif t == "Vector3" or t == "CFrame" or t == "Color3" or t == "UDim2" then
+        return tostring(v)
+    elseif t == "table" then
+        if depth <= 0 then return "table" end
+        local parts, n = {}, 0
+        for k, val in pairs(v) do
+            n = n + 1
+            if n > 20 then table.insert(parts, "..."); break end
+            local key = type(k) == "string" and k or "[" .. tostring(k) .. "]"
+            table.insert(parts, key .. "=" .. formatValue(val, depth - 1))
         end
+        return "{" .. table.concat(parts, ", ") .. "}"
+    end
+    return t
+end
+
+-- format args to a readable string, capped at 200 chars (1000 when tables are expanded)
+local function formatArgs(args, depth)
+    local parts = {}
+    for _, v in ipairs(args) do
+        table.insert(parts, formatValue(v, depth))
     end
+    local limit = depth > 0 and 1000 or 200
     local result = table.concat(parts, ", ")
-    if #result > 200 then result = string.sub(result, 1, 197) .. "..." end
+    if #result > limit then result = string.sub(result, 1, limit - 3) .. "..." end
     return result
 end
 
@@ -90,6 +105,15 @@ local subscriptions = {}
 local connections = {}
 local active = true
 
+-- arg depth for a path: the deepest matching subscription, else MAX_ARG_DEPTH
+local function argDepth(path)
+    local depth
+    for sub, d in pairs(subscriptions) do
+        if string.find(path, sub, 1, true) and (depth == nil or d > depth) then depth = d end
+    end
+    return depth or MAX_ARG_DEPTH
+end
+
 local function isSubscribed(path)
     if subscriptions[path] then return true end
     -- partial match: subscribing to "Remotes" matches "Remotes.SetAFK"
@@ -107,7 +131,7 @@ local function sendSpy(direction, method, remotePath, args)
     seen[key] = true
 
     local argStr = ""
-    pcall(function() argStr = formatArgs(args) end)
+    pcall(function() argStr = formatArgs(args, argDepth(remotePath)) end)
 
     local tag = string.lower(direction)
     send({
@@ -207,8 +231,8 @@ end))
 getgenv().__XENO_SPY = {
     active = true,
     subscriptions = subscriptions,
-    Subscribe = function(path)
-        subscriptions[path] = true
+    Subscribe = function(path, depth)
+        subscriptions[path] = depth or MAX_ARG_DEPTH
         -- clear seen entries for this path so they start logging again
         for key in pairs(seen) do
             if string.find(key, path, 1, true) then
@@ -0,0 +1,204 @@
+-- Reduced-capability spy for executors without UNC hooks (Xeno, via --spy-allow-xeno).
+-- Without hookfunction/hookmetamethod outgoing FireServer/InvokeServer calls can't be
+-- intercepted, so only incoming OnClientEvent traffic is observed. Entries are tagged
+-- "degraded" so they can be told apart from full spy output.
+local HttpService = game:GetService("HttpService")
+local Players     = game:GetService("Players")
+local StarterGui  = game:GetService("StarterGui")
+local localPlayer = Players.LocalPlayer
+
+local SERVER_URL = "http://localhost:{{PORT}}/internal"
+local SECRET     = "{{SECRET}}"
+-- how many levels of nested tables to expand in logged args (per subscription overridable)
+local MAX_ARG_DEPTH = {{MAX_ARG_DEPTH}}
+local USERNAME   = localPlayer.Name
+
+local function makeHeaders()
+    local h = { ["Content-Type"] = "application/json" }
+    if SECRET ~= "" then h["X-Xeno-Secret"] = SECRET end
+    return h
+end
+
+local function send(payload)
+    payload.username = USERNAME
+    pcall(function()
+        request({
+            Url     = SERVER_URL,
+            Method  = "POST",
+            Headers = makeHeaders(),
+            Body    = HttpService:JSONEncode(payload)
+        })
+    end)
+end
+
+local function notify(text, duration)
+    pcall(function()
+        StarterGui:SetCore("SendNotification", {
+            Title = "xeno-mcp spy",
+            Text = text,
+            Duration = duration or 5,
+        })
+    end)
+end
+
+-- format one value; tables are expanded up to `depth` levels (20 entries each)
+local function formatValue(v, depth)
+    local t = typeof(v)
+    if t == "string" then
+        local s = string.sub(v, 1, 40)
+        if #v > 40 then s = s .. "..." end
+        return '"' .. s .. '"'
+    elseif t == "number" or t == "boolean" then
+        return tostring(v)
+    elseif t == "Instance" then
+        return t .. "<" .. v.ClassName .. ">"
+    elseif t == "EnumItem" then
+        return tostring(v)
+    elseif t == "Vector3" or t == "CFrame" or t == "Color3" or t == "UDim2" then
+        return tostring(v)
+    elseif t == "table" then
+        if depth <= 0 then return "table" end
+        local parts, n = {}, 0
+        for k, val in pairs(v) do
+            n = n + 1
+            if n > 20 then table.insert(parts, "..."); break end
+            local key = type(k) == "string" and k or "[" .. tostring(k) .. "]"
+            table.insert(parts, key .. "=" .. formatValue(val, depth - 1))
+        end
+        return "{" .. table.concat(parts, ", ") .. "}"
+    end
+    return t
+end
+
+-- format args to a readable string, capped at 200 chars (1000 when tables are expanded)
+local function formatArgs(args, depth)
+    local parts = {}
+    for _, v in ipairs(args) do
+        table.insert(parts, formatValue(v, depth))
+    end
+    local limit = depth > 0 and 1000 or 200
+    local result = table.concat(parts, ", ")
+    if #result > limit then result = string.sub(result, 1, limit - 3) .. "..." end
+    return result
+end
+
+-- cleanup previous spy if running
+if getgenv().__XENO_SPY then
+    pcall(function() getgenv().__XENO_SPY.Disconnect() end)
+end
+
+-- state
+local seen = {}
+local subscriptions = {}
+local connections = {}
+local active = true
+
+-- arg depth for a path: the deepest matching subscription, else MAX_ARG_DEPTH
+local function argDepth(path)
+    local depth
+    for sub, d in pairs(subscriptions) do
+        if string.find(path, sub, 1, true) and (depth == nil or d > depth) then depth = d end
+    end
+    return depth or MAX_ARG_DEPTH
+end
+
+local function isSubscribed(path)
+    if subscriptions[path] then return true end
+    -- partial match: subscribing to "Remotes" matches "Remotes.SetAFK"
+    for sub in pairs(subscriptions) do
+        if string.find(path, sub, 1, true) then return true end
+    end
+    return false
+end
+
+local function sendSpy(direction, method, remotePath, args)
+    local key = direction .. ":" .. method .. ":" .. remotePath
+    local subscribed = isSubscribed(remotePath)
+
+    if not subscribed and seen[key] then return end
+    seen[key] = true
+
+    local argStr = ""
+    pcall(function() argStr = formatArgs(args, argDepth(remotePath)) end)
+
+    local tag = string.lower(direction)
+    send({
+        event   = "spy",
+        level   = "info",
+        message = string.format("[%s] [%s] %s(%s)", direction, method, remotePath, argStr),
+        source  = "remote_spy",
+        tags    = {"spy", tag, "degraded"}
+    })
+end
+
+-- IN hooks: passive OnClientEvent listeners
+local function hookRemote(remote)
+    if not remote:IsA("RemoteEvent") then return end
+    local path = remote:GetFullName()
+    local conn = remote.OnClientEvent:Connect(function(...)
+        if not active then return end
+        local args = {...}
+        pcall(function()
+            sendSpy("IN", "OnClientEvent", path, args)
+        end)
+    end)
+    table.insert(connections, conn)
+end
+
+for _, v in ipairs(game:GetDescendants()) do
+    if v:IsA("RemoteEvent") then pcall(hookRemote, v) end
+end
+table.insert(connections, game.DescendantAdded:Connect(function(v)
+    if v:IsA("RemoteEvent") then pcall(hookRemote, v) end
+end))
+
+-- detect player leaving
+table.insert(connections, Players.PlayerRemoving:Connect(function(p)
+    if p == localPlayer then
+        send({ event = "spy_detached" })
+        active = false
+    end
+end))
+
+-- public API on getgenv()
+getgenv().__XENO_SPY = {
+    active = true,
+    subscriptions = subscriptions,
+    Subscribe = function(path, depth)
+        subscriptions[path] = depth or MAX_ARG_DEPTH
+        -- clear seen entries for this path so they start logging again
+        for key in pairs(seen) do
+            if string.find(key, path, 1, true) then
+                seen[key] = nil
+            end
+        end
+        print("[SPY] Subscribed: " .. path)
+    end,
+    Unsubscribe = function(path)
+        subscriptions[path] = nil
+        print("[SPY] Unsubscribed: " .. path)
+    end,
+    Reset = function()
+        seen = {}
+        print("[SPY] Reset dedup cache")
+    end,
+    Count = function()
+        local c = 0
+        for _ in pairs(seen) do c = c + 1 end
+        print("[SPY] " .. c .. " unique remotes seen")
+        return c
+    end,
+    Disconnect = function()
+        active = false
+        for _, c in ipairs(connections) do
+            pcall(function() c:Disconnect() end)
+        end
+        connections = {}
+        getgenv().__XENO_SPY = nil
+        print("[SPY] Disconnected")
+    end
+}
+
+send({ event = "spy_attached" })
+notify("Remote spy active (incoming only)", 4)
+print("[SPY] Remote spy attached (degraded: incoming OnClientEvent only)")
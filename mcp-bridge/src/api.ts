@@ -27,8 +27,14 @@ export async function apiPost(path: string, body: any): Promise<any> {
   return res.json();
 }
 
-export async function apiDelete(path: string): Promise<any> {
-  const res = await fetch(new URL(path, BASE_URL).toString(), {
+export async function apiDelete(path: string, params?: Record<string, string>): Promise<any> {
+  const url = new URL(path, BASE_URL);
+  if (params) {
+    for (const [k, v] of Object.entries(params)) {
+      if (v !== undefined && v !== "") url.searchParams.set(k, v);
+    }
+  }
+  const res = await fetch(url.toString(), {
     method: "DELETE",
     headers: headers(),
   });
@@ -23,9 +23,8 @@ async function ensureHttpServer(): Promise<void> {
   // Binary lives at xeno-mcp/target/release/xeno-mcp.exe relative to mcp-bridge/dist/
   const exe = resolve(bridgeDir, "..", "..", "target", "release", "xeno-mcp.exe");
 
+  // XENO_MCP_SECRET is inherited by the child, which keeps it out of the process list
   const args = ["--console"];
-  const secret = process.env.XENO_MCP_SECRET;
-  if (secret) args.push("--secret", secret);
 
   const mode = process.env.XENO_MCP_MODE;
   if (mode) args.push("--mode", mode);
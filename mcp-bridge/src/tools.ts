@@ -16,7 +16,7 @@ function text(content: string) {
 function formatError(data: any): string {
   if (data?.error) {
     const err = String(data.error);
-    if (err.includes("Cannot reach Xeno") || err.includes("localhost:3110")) {
+    if (err.includes("Cannot reach Xeno") || err.includes("Xeno request timed out") || err.includes("localhost:3110")) {
       return `The executor is not reachable. In Xeno mode, make sure the Xeno application is open and injected. In generic mode, this error should not appear — check if the xeno-mcp server is running.\n\nDo NOT retry automatically — this requires the user to take action.\n\nOriginal error: ${err}`;
     }
     return `Error: ${err}${data.not_found ? `\nNot found PIDs: ${JSON.stringify(data.not_found)}` : ""}${data.not_attached ? `\nNot attached: ${JSON.stringify(data.not_attached)}` : ""}`;
@@ -107,15 +107,25 @@ export function registerTools(server: McpServer) {
     "get_clients",
     `List all connected Roblox clients.
 In Xeno mode: returns each client as "Username(PID)" with their status and logger state.
-In generic mode: returns clients by username with connection and heartbeat info.
+In generic mode: returns clients by username with connection and heartbeat info, logger_attached (always true) and last_log_at.
 
 Use these identifiers for execute_lua and attach_logger.
 
+Optional filters: attached_only (status Attached), with_logger (true/false) and a case-insensitive username substring. Generic mode only honours username.
+
 IMPORTANT: Before executing scripts or reading logs, check if the logger is attached (Xeno mode) or if a client is connected (generic mode). If not, guide the user through setup.`,
-    {},
-    async () => {
+    {
+      attached_only: z.boolean().optional().describe("Only clients in the Attached state (Xeno mode)"),
+      with_logger: z.boolean().optional().describe("Only clients with (true) or without (false) the logger attached (Xeno mode)"),
+      username: z.string().optional().describe("Case-insensitive username substring"),
+    },
+    async ({ attached_only, with_logger, username }) => {
       try {
-        const data = await apiGet("/clients");
+        const params: Record<string, string> = {};
+        if (attached_only) params.attached_only = "true";
+        if (with_logger !== undefined) params.with_logger = String(with_logger);
+        if (username) params.username = username;
+        const data = await apiGet("/clients", params);
         if (!data?.ok) return text(formatError(data));
 
         // Generic mode returns different client format
@@ -198,12 +208,14 @@ VARARG GOTCHA IN LUAU:
 CLIENT IDENTIFICATION:
 - Pass clients as "Username(PID)" (e.g. "Lypt1x(35540)"), username, or PID
 - Prefer the "Username(PID)" format from get_clients
-- If you pass an EMPTY clients array and there is exactly ONE connected client, it will be auto-selected`,
+- If you pass an EMPTY clients array and there is exactly ONE connected client, it will be auto-selected
+- Pass ["*"] to run on every attached client (Xeno mode)`,
     {
-      script: z.string().describe("The Lua script to execute. Must be valid Luau code."),
+      script: z.string().optional().describe("The Lua script to execute. Must be valid Luau code. Omit when using script_name."),
+      script_name: z.string().optional().describe("Run a script saved with save_script instead of an inline one"),
       clients: z.array(z.string()).optional().describe('Client identifiers — use "Username(PID)" format from get_clients, or just username or PID. Leave empty to auto-select if only one client is connected.'),
     },
-    async ({ script, clients: identifiers }) => {
+    async ({ script, script_name, clients: identifiers }) => {
       try {
         const allClients = await fetchClients();
 
@@ -219,13 +231,16 @@ CLIENT IDENTIFICATION:
           return text(`Multiple clients connected. Please specify which client(s) to target:\n${allClients.map(c => `  - ${c.label}`).join("\n")}`);
         }
 
-        const { pids, errors } = resolveIdentifiers(resolvedIdentifiers, allClients);
+        // "*" is passed through: the server expands it to every attached client
+        const { pids, errors } = resolvedIdentifiers.length === 1 && resolvedIdentifiers[0] === "*"
+          ? { pids: ["*"], errors: [] }
+          : resolveIdentifiers(resolvedIdentifiers, allClients);
 
         if (errors.length > 0) {
           return text(`Error resolving clients:\n${errors.join("\n")}\n\nAvailable clients: ${allClients.map(c => c.label).join(", ") || "none"}`);
         }
 
-        const data = await apiPost("/execute", { script, pids });
+        const data = await apiPost("/execute", { script: script ?? "", script_name, pids });
         if (!data.ok) return text(formatError(data));
 
         // In generic mode, poll for script output to give instant feedback
@@ -265,6 +280,27 @@ CLIENT IDENTIFICATION:
     }
   );
 
+  server.tool(
+    "get_execution_result",
+    `Get the outcome of a script executed in generic mode, using the file_id returned by execute_lua.
+
+Returns status "pending" until the loader reports back, then "done" (with captured output lines and stringified return values) or "error" (runtime error, compile error, or rejected signature). Returns a 404 error once the server's result timeout has passed without a result.
+
+Only output printed while the script ran synchronously is captured — use get_logs for output from spawned threads or event handlers.`,
+    {
+      file_id: z.string().describe("The file_id from execute_lua's response"),
+    },
+    async ({ file_id }) => {
+      try {
+        const data = await apiGet(`/execute/${encodeURIComponent(file_id)}/result`);
+        if (!data.ok) return text(formatError(data));
+        return text(JSON.stringify(data.result, null, 2));
+      } catch (e: any) {
+        return text(formatCatchError(e));
+      }
+    }
+  );
+
   server.tool(
     "attach_logger",
     `Attach the log-forwarding script to one or more Roblox clients. Once attached, all Roblox output (print, warn, error) from those clients is captured and forwarded to the xeno-mcp server. You can then query logs with get_logs.
@@ -277,6 +313,48 @@ The logger:
 
 Clients must be "Attached" (status 3). If already attached, returns info without re-attaching.
 
+Set all=true to target every client that is currently "Attached" instead of listing them; the response's "targets" shows who was resolved.
+
+CLIENT IDENTIFICATION:
+- Pass clients as "Username(PID)" (e.g. "Lypt1x(35540)"), username, or PID
+- Prefer the "Username(PID)" format from get_clients`,
+    {
+      clients: z.array(z.string()).optional().describe('Client identifiers — use "Username(PID)" format from get_clients, or just username or PID. Omit when all is true.'),
+      all: z.boolean().optional().describe("Attach to every client currently in the Attached state"),
+    },
+    async ({ clients: identifiers, all }) => {
+      try {
+        if (all) {
+          const data = await apiPost("/attach-logger", { all: true });
+          if (!data.ok) return text(formatError(data));
+          return text(JSON.stringify(data, null, 2));
+        }
+        if (!identifiers || identifiers.length === 0) {
+          return text("Pass at least one client, or set all=true.");
+        }
+
+        const allClients = await fetchClients();
+        const { pids, errors } = resolveIdentifiers(identifiers, allClients);
+
+        if (errors.length > 0) {
+          return text(`Error resolving clients:\n${errors.join("\n")}\n\nAvailable clients: ${allClients.map(c => c.label).join(", ") || "none"}`);
+        }
+
+        const data = await apiPost("/attach-logger", { pids });
+        if (!data.ok) return text(formatError(data));
+        return text(JSON.stringify(data, null, 2));
+      } catch (e: any) {
+        return text(formatCatchError(e));
+      }
+    }
+  );
+
+  server.tool(
+    "detach_logger",
+    `Detach the log-forwarding script from one or more Roblox clients (Xeno mode only). Output from those clients stops being forwarded; logs already captured are kept.
+
+The response lists which clients were "detached" and which did not have the logger attached ("not_attached"). Use attach_logger to re-attach later.
+
 CLIENT IDENTIFICATION:
 - Pass clients as "Username(PID)" (e.g. "Lypt1x(35540)"), username, or PID
 - Prefer the "Username(PID)" format from get_clients`,
@@ -292,7 +370,7 @@ CLIENT IDENTIFICATION:
           return text(`Error resolving clients:\n${errors.join("\n")}\n\nAvailable clients: ${allClients.map(c => c.label).join(", ") || "none"}`);
         }
 
-        const data = await apiPost("/attach-logger", { pids });
+        const data = await apiPost("/detach-logger", { pids });
         if (!data.ok) return text(formatError(data));
         return text(JSON.stringify(data, null, 2));
       } catch (e: any) {
@@ -313,17 +391,26 @@ PAGINATION: Results are paginated with 50 logs per page by default (max: 1000).
 - Use the "page" parameter (1-indexed) to navigate pages. Page 1 is the first page.
 - Alternatively use "offset" for manual offset-based pagination.
 - Check "has_more" in the response to know if there are more pages.
+- When polling for new output, pass "next_cursor" from the previous response as "cursor" to avoid duplicates or gaps.
 - Use "total_pages" to know the last page number.
 - Results are sorted newest-first by default.`,
     {
       level: z.string().optional().describe("Filter by log level: 'info', 'warn', 'error', 'output', or 'script'"),
+      min_level: z.string().optional().describe("Keep this level and above (trace < debug < info < output < script < warn < error), e.g. 'warn' for warnings and errors"),
       source: z.string().optional().describe("Filter by source (substring match)"),
       search: z.string().optional().describe("Search log messages (substring match, case-insensitive)"),
+      search_mode: z.string().optional().describe("How 'search' is matched: 'substring' (default) or 'regex' (case-sensitive; prefix with (?i) to ignore case)"),
       tag: z.string().optional().describe("Filter by tags (comma-separated)"),
+      tag_mode: z.string().optional().describe("'any' (default): match at least one tag; 'all': match every tag"),
+      tag_exclude: z.string().optional().describe("Drop logs carrying any of these tags (comma-separated). Wins over 'tag'"),
       pid: z.string().optional().describe("Filter by client PID"),
+      field: z.string().optional().describe("Exact match on a structured field, as key:value (e.g. 'region:us-east')"),
+      data_path: z.string().optional().describe("Dotted path into the entry's JSON data (e.g. 'item.id', 'items.0.name'). Alone, matches entries where the path exists"),
+      data_value: z.string().optional().describe("With data_path, exact value to match at that path (e.g. '42')"),
       page: z.number().optional().describe("Page number (1-indexed). Default: 1. Use this for easy pagination."),
       limit: z.number().optional().describe("Results per page (default: 50, max: 1000)"),
       offset: z.number().optional().describe("Skip this many results (alternative to page-based pagination)"),
+      cursor: z.string().optional().describe("Resume after this log id (pass next_cursor from the previous response). Stable while new logs arrive."),
       order: z.string().optional().describe("Sort order: 'asc' (oldest first) or 'desc' (newest first, default)"),
       after: z.string().optional().describe("Only logs after this ISO 8601 timestamp"),
       before: z.string().optional().describe("Only logs before this ISO 8601 timestamp"),
@@ -332,13 +419,21 @@ PAGINATION: Results are paginated with 50 logs per page by default (max: 1000).
       try {
         const queryParams: Record<string, string> = {};
         if (params.level) queryParams.level = params.level;
+        if (params.min_level) queryParams.min_level = params.min_level;
         if (params.source) queryParams.source = params.source;
         if (params.search) queryParams.search = params.search;
+        if (params.search_mode) queryParams.search_mode = params.search_mode;
         if (params.tag) queryParams.tag = params.tag;
+        if (params.tag_mode) queryParams.tag_mode = params.tag_mode;
+        if (params.tag_exclude) queryParams.tag_exclude = params.tag_exclude;
         if (params.pid) queryParams.pid = params.pid;
+        if (params.field) queryParams.field = params.field;
+        if (params.data_path) queryParams.data_path = params.data_path;
+        if (params.data_value !== undefined) queryParams.data_value = params.data_value;
         if (params.page !== undefined) queryParams.page = String(params.page);
         if (params.limit !== undefined) queryParams.limit = String(params.limit);
         if (params.offset !== undefined) queryParams.offset = String(params.offset);
+        if (params.cursor) queryParams.cursor = params.cursor;
         if (params.order) queryParams.order = params.order;
         if (params.after) queryParams.after = params.after;
         if (params.before) queryParams.before = params.before;
@@ -377,12 +472,95 @@ PAGINATION: Results are paginated with 50 logs per page by default (max: 1000).
   );
 
   server.tool(
-    "clear_logs",
-    "Clear all stored logs from the xeno-mcp server. This is irreversible.",
+    "get_log_stats",
+    "Summarize stored logs without fetching them: total count, counts by level, source and PID, and the oldest/newest timestamps. Accepts the same filters as get_logs (e.g. after/before for a time window). Use this to decide whether a detailed get_logs call is worth it.",
+    {
+      level: z.string().optional().describe("Filter by log level"),
+      source: z.string().optional().describe("Filter by source (substring match)"),
+      pid: z.string().optional().describe("Filter by client PID"),
+      after: z.string().optional().describe("Only logs after this ISO 8601 timestamp"),
+      before: z.string().optional().describe("Only logs before this ISO 8601 timestamp"),
+    },
+    async (params) => {
+      try {
+        const data = await apiGet("/logs/stats", params as Record<string, string>);
+        if (!data.ok) return text(formatError(data));
+        return text(JSON.stringify(data, null, 2));
+      } catch (e: any) {
+        return text(formatCatchError(e));
+      }
+    }
+  );
+
+  server.tool(
+    "tail_logs",
+    "Return the most recent log entries (oldest first), like `tail`. Faster than get_logs for a quick \"what just happened\" check; only pid and level filters are supported.",
+    {
+      n: z.number().optional().describe("How many entries to return (default 50, max 1000)"),
+      pid: z.string().optional().describe("Filter by client PID"),
+      level: z.string().optional().describe("Filter by log level"),
+    },
+    async ({ n, pid, level }) => {
+      try {
+        const params: Record<string, string> = {};
+        if (n !== undefined) params.n = String(n);
+        if (pid) params.pid = pid;
+        if (level) params.level = level;
+        const data = await apiGet("/logs/tail", params);
+        if (!data.ok) return text(formatError(data));
+        return text(JSON.stringify(data, null, 2));
+      } catch (e: any) {
+        return text(formatCatchError(e));
+      }
+    }
+  );
+
+  server.tool(
+    "save_script",
+    "Save a Lua script under a name in the server's script library so it can be re-run later via execute_lua's script_name instead of pasting it again. Saving under an existing name overwrites it. Names may not contain path separators.",
+    {
+      name: z.string().describe("Library name, e.g. \"diag\""),
+      script: z.string().describe("The Lua source to save"),
+    },
+    async ({ name, script }) => {
+      try {
+        const data = await apiPost("/scripts", { name, script });
+        if (!data.ok) return text(formatError(data));
+        return text(JSON.stringify(data, null, 2));
+      } catch (e: any) {
+        return text(formatCatchError(e));
+      }
+    }
+  );
+
+  server.tool(
+    "list_saved_scripts",
+    "List the scripts saved in the server's script library, with their sizes in bytes.",
     {},
     async () => {
       try {
-        const data = await apiDelete("/logs");
+        const data = await apiGet("/scripts");
+        if (!data.ok) return text(formatError(data));
+        return text(JSON.stringify(data.scripts, null, 2));
+      } catch (e: any) {
+        return text(formatCatchError(e));
+      }
+    }
+  );
+
+  server.tool(
+    "clear_logs",
+    "Clear stored logs from the xeno-mcp server. With no filters every log is removed; with filters only matching logs are removed and the rest are kept. This is irreversible.",
+    {
+      level: z.string().optional().describe("Only remove logs with this level"),
+      source: z.string().optional().describe("Only remove logs whose source contains this (substring match)"),
+      tag: z.string().optional().describe("Only remove logs carrying any of these tags (comma-separated)"),
+      pid: z.string().optional().describe("Only remove logs from this client PID"),
+      before: z.string().optional().describe("Only remove logs before this ISO 8601 timestamp"),
+    },
+    async (params) => {
+      try {
+        const data = await apiDelete("/logs", params as Record<string, string>);
         if (!data.ok) return text(formatError(data));
         return text(JSON.stringify(data, null, 2));
       } catch (e: any) {
@@ -404,7 +582,10 @@ The loader includes the logger — no separate attach_logger step is needed in g
     {},
     async () => {
       try {
-        const resp = await fetch(`http://localhost:${process.env.XENO_MCP_PORT || 3111}/loader-script`);
+        const secret = process.env.XENO_MCP_SECRET;
+        const resp = await fetch(`http://localhost:${process.env.XENO_MCP_PORT || 3111}/loader-script`, {
+          headers: secret ? { "X-Xeno-Secret": secret } : {},
+        });
         const script = await resp.text();
         return text(`INTERNAL: Raw loader script source. Do NOT paste this into the chat for the user. Instead, tell them to run:\n\nloadstring(game:HttpGet("http://localhost:3111/loader-script"))()\n\n---\n\n${script}`);
       } catch (e: any) {
@@ -675,10 +856,11 @@ CLEANUP:
 - The spy auto-cleans up when the player leaves the game`,
     {
       pids: z.array(z.string()).optional().describe('Client identifiers (Xeno mode only). In generic mode, omit this — the spy script is sent via the exchange directory.'),
+      max_arg_depth: z.number().optional().describe('Levels of nested argument tables to expand in logged calls (default 2, max 8). 0 logs tables as just "table".'),
     },
-    async ({ pids }) => {
+    async ({ pids, max_arg_depth }) => {
       try {
-        const data = await apiPost("/spy/attach", { pids: pids || [] });
+        const data = await apiPost("/spy/attach", { pids: pids || [], max_arg_depth });
         if (!data.ok) return text(formatError(data));
         return text(JSON.stringify(data, null, 2));
       } catch (e: any) {
@@ -714,14 +896,58 @@ Use this when you need to track the data being sent to or received from a specif
 
 Supports partial matching: subscribing to "Remotes" will match "Remotes.SetAFK", "Remotes.BuyItem", etc.
 
+Pass several paths at once with "paths" instead of calling this tool repeatedly.
+
 Example paths: "ReplicatedStorage.Remotes.SetAFK", "ReplicatedStorage.TS.GeneratedNetworkRemotes"`,
     {
-      path: z.string().describe('The remote path (or partial path) to subscribe to. Supports partial matching.'),
+      path: z.string().optional().describe('The remote path (or partial path) to subscribe to. Supports partial matching.'),
+      paths: z.array(z.string()).optional().describe('Several remote paths to subscribe to in one call. Combined with "path" if both are given.'),
       pids: z.array(z.string()).optional().describe('Client identifiers (Xeno mode only).'),
+      max_arg_depth: z.number().optional().describe('Levels of nested argument tables to expand for these remotes (default 2, max 8). Lower it for remotes with huge payloads.'),
     },
-    async ({ path, pids }) => {
+    async ({ path, paths, pids, max_arg_depth }) => {
+      try {
+        const data = await apiPost("/spy/subscribe", { path, paths: paths || [], pids: pids || [], max_arg_depth });
+        if (!data.ok) return text(formatError(data));
+        return text(JSON.stringify(data, null, 2));
+      } catch (e: any) {
+        return text(formatCatchError(e));
+      }
+    }
+  );
+
+  server.tool(
+    "get_spy_calls",
+    `List remote calls captured by the spy, oldest first, without other log noise. Each call has direction ("in"/"out"), method, path, args, username, PID and timestamp.
+
+Use "path" for a partial match on the remote path. To poll for new traffic, pass the "cursor" from the previous response as "since".`,
+    {
+      path: z.string().optional().describe('Only calls whose remote path contains this text.'),
+      since: z.string().optional().describe('Cursor from a previous get_spy_calls response; only newer calls are returned.'),
+      limit: z.number().optional().describe('Maximum calls to return (default 100, max 1000).'),
+    },
+    async ({ path, since, limit }) => {
+      try {
+        const params: Record<string, string> = {};
+        if (path) params.path = path;
+        if (since) params.since = since;
+        if (limit !== undefined) params.limit = String(limit);
+        const data = await apiGet("/spy/calls", params);
+        if (!data.ok) return text(formatError(data));
+        return text(JSON.stringify(data, null, 2));
+      } catch (e: any) {
+        return text(formatCatchError(e));
+      }
+    }
+  );
+
+  server.tool(
+    "clear_spy_calls",
+    `Delete the spy-captured calls stored so far and reset the per-path call counts, without detaching the spy. Subscriptions stay in place, so new calls keep being captured. Use this to start from a clean slate while debugging.`,
+    {},
+    async () => {
       try {
-        const data = await apiPost("/spy/subscribe", { path, pids: pids || [] });
+        const data = await apiPost("/spy/clear", {});
         if (!data.ok) return text(formatError(data));
         return text(JSON.stringify(data, null, 2));
       } catch (e: any) {
@@ -734,12 +960,13 @@ Example paths: "ReplicatedStorage.Remotes.SetAFK", "ReplicatedStorage.TS.Generat
     "spy_unsubscribe",
     `Unsubscribe from a remote path, returning it to dedup-only mode. After unsubscribing, only the first occurrence of calls to this remote will be logged again.`,
     {
-      path: z.string().describe('The remote path to unsubscribe from.'),
+      path: z.string().optional().describe('The remote path to unsubscribe from.'),
+      paths: z.array(z.string()).optional().describe('Several remote paths to unsubscribe from in one call. Combined with "path" if both are given.'),
       pids: z.array(z.string()).optional().describe('Client identifiers (Xeno mode only).'),
     },
-    async ({ path, pids }) => {
+    async ({ path, paths, pids }) => {
       try {
-        const data = await apiPost("/spy/unsubscribe", { path, pids: pids || [] });
+        const data = await apiPost("/spy/unsubscribe", { path, paths: paths || [], pids: pids || [] });
         if (!data.ok) return text(formatError(data));
         return text(JSON.stringify(data, null, 2));
       } catch (e: any) {
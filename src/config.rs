@@ -0,0 +1,158 @@
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches};
+use serde::Deserialize;
+
+use crate::models::{Args, ServerMode};
+
+/// `--config` file contents. Keys are the long flag names with underscores
+/// (`xeno_url = "http://localhost:3110"`); every key is optional and unknown
+/// keys are rejected so typos don't go unnoticed.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileConfig {
+    port: Option<u16>,
+    bind: Option<String>,
+    console: Option<bool>,
+    log_file: Option<String>,
+    log_file_max_bytes: Option<u64>,
+    log_file_keep: Option<usize>,
+    restore_logs: Option<bool>,
+    secret: Option<String>,
+    secret_file: Option<String>,
+    gate_reads: Option<bool>,
+    cors_origin: Option<Vec<String>>,
+    max_entries: Option<usize>,
+    spy_events_max: Option<usize>,
+    log_max_age_secs: Option<u64>,
+    dedup_window_ms: Option<u64>,
+    dedup_logs: Option<bool>,
+    spy_allow_xeno: Option<bool>,
+    auto_reattach: Option<bool>,
+    execute_rate: Option<f64>,
+    execute_rate_per_min: Option<u32>,
+    execute_burst: Option<u32>,
+    execute_denylist: Option<Vec<String>>,
+    xeno_url: Option<String>,
+    xeno_clients_path: Option<String>,
+    xeno_execute_path: Option<String>,
+    xeno_timeout_ms: Option<u64>,
+    xeno_retries: Option<u32>,
+    xeno_poll_secs: Option<u64>,
+    #[serde(alias = "client_cache_ms")]
+    clients_cache_ms: Option<u64>,
+    clients_poll_ms: Option<u64>,
+    mode: Option<ServerMode>,
+    exchange_dir: Option<String>,
+    heartbeat_timeout_secs: Option<u64>,
+    result_timeout_secs: Option<u64>,
+    shutdown_timeout_secs: Option<u64>,
+    storage_dir: Option<String>,
+    executor_exchange_dir: Option<String>,
+}
+
+/// Parse the command line, then fill every option that wasn't given there
+/// from the `--config` file. Precedence: command line > file > built-in defaults.
+pub fn load_args() -> Result<Args, String> {
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches).map_err(|e| e.to_string())?;
+
+    if let Some(path) = args.config.clone() {
+        apply_file(&mut args, &matches, &path)?;
+    }
+    resolve_secret(&mut args)?;
+    validate(&args)?;
+    Ok(args)
+}
+
+fn validate(args: &Args) -> Result<(), String> {
+    for (flag, path) in [
+        ("--xeno-clients-path", &args.xeno_clients_path),
+        ("--xeno-execute-path", &args.xeno_execute_path),
+    ] {
+        if !path.starts_with('/') {
+            return Err(format!("{} must start with '/', got '{}'", flag, path));
+        }
+    }
+    // clap rejects both on the command line, but a config file can still set both
+    if args.execute_rate.is_some() && args.execute_rate_per_min.is_some() {
+        return Err("--execute-rate and --execute-rate-per-min can't be combined".to_string());
+    }
+    if let Some(rate) = args.execute_rate.filter(|r| !r.is_finite() || *r <= 0.0) {
+        return Err(format!("--execute-rate must be a positive number, got {}", rate));
+    }
+    if args.execute_rate_per_min == Some(0) {
+        return Err("--execute-rate-per-min must be at least 1".to_string());
+    }
+    Ok(())
+}
+
+fn apply_file(args: &mut Args, matches: &clap::ArgMatches, path: &str) -> Result<(), String> {
+    let raw = std::fs::read_to_string(path)
+        .map_err(|e| format!("cannot read config file '{}': {}", path, e))?;
+    let file: FileConfig = toml::from_str(&raw)
+        .map_err(|e| format!("invalid config file '{}': {}", path, e))?;
+
+    let from_cli = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+    macro_rules! merge {
+        ($($field:ident),* $(,)?) => {$(
+            if let Some(v) = file.$field {
+                if !from_cli(stringify!($field)) {
+                    args.$field = v;
+                }
+            }
+        )*};
+    }
+    macro_rules! merge_opt {
+        ($($field:ident),* $(,)?) => {$(
+            if file.$field.is_some() && !from_cli(stringify!($field)) {
+                args.$field = file.$field;
+            }
+        )*};
+    }
+
+    merge!(
+        port, bind, console, log_file_keep, restore_logs, gate_reads, max_entries, spy_events_max, dedup_logs, spy_allow_xeno, auto_reattach,
+        xeno_url, xeno_clients_path, xeno_execute_path, xeno_timeout_ms, xeno_retries, clients_cache_ms, clients_poll_ms, mode, exchange_dir,
+        heartbeat_timeout_secs, result_timeout_secs, shutdown_timeout_secs, storage_dir,
+        execute_denylist, cors_origin,
+    );
+    merge_opt!(
+        log_file, log_file_max_bytes, secret, secret_file, log_max_age_secs, dedup_window_ms,
+        execute_burst, xeno_poll_secs, executor_exchange_dir,
+    );
+    // The two rate flags are one setting in different units: a rate given on the
+    // command line replaces whichever rate the file sets.
+    if !from_cli("execute_rate") && !from_cli("execute_rate_per_min") {
+        merge_opt!(execute_rate, execute_rate_per_min);
+    }
+    Ok(())
+}
+
+/// The secret can come from `--secret`, `--secret-file` or `XENO_MCP_SECRET`.
+/// Any that are set must agree; the first in that order is used. An empty
+/// value is an error rather than silently disabling authentication.
+fn resolve_secret(args: &mut Args) -> Result<(), String> {
+    let mut sources: Vec<(&str, String)> = Vec::new();
+    if let Some(secret) = args.secret.take() {
+        sources.push(("--secret", secret));
+    }
+    if let Some(ref path) = args.secret_file {
+        let raw = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read secret file '{}': {}", path, e))?;
+        sources.push(("--secret-file", raw.trim_end().to_string()));
+    }
+    if let Ok(secret) = std::env::var("XENO_MCP_SECRET") {
+        sources.push(("XENO_MCP_SECRET", secret));
+    }
+
+    if let Some((name, _)) = sources.iter().find(|(_, v)| v.is_empty()) {
+        return Err(format!("secret from {} is empty", name));
+    }
+    if let Some((first, value)) = sources.first() {
+        if let Some((other, _)) = sources.iter().find(|(_, v)| v != value) {
+            return Err(format!("conflicting secrets: {} and {} differ", first, other));
+        }
+    }
+    args.secret = sources.into_iter().next().map(|(_, v)| v);
+    Ok(())
+}
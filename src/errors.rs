@@ -12,9 +12,12 @@ pub async fn not_found_handler(req: HttpRequest) -> HttpResponse {
     json_error(
         actix_web::http::StatusCode::NOT_FOUND,
         &format!(
-            "No endpoint matches {} {}. Available endpoints: GET /health, GET /clients, \
-             POST /execute, POST /attach-logger, POST /internal, \
-             GET /logs, DELETE /logs",
+            "No endpoint matches {} {}. Available endpoints: GET /health, GET /metrics, GET /clients, \
+             GET /clients/ws, GET /clients/{{pid}}, GET /clients/{{pid}}/logs, POST /execute, GET /execute/{{id}}/result, \
+             GET /scripts, POST /scripts, POST /attach-logger, POST /detach-logger, POST /internal, POST /heartbeat, \
+             GET /logs, POST /logs, DELETE /logs, GET /logs/stream, GET /logs/stats, GET /logs/tail, POST /logs/export, \
+             GET /loader-script, POST /verify-script, POST /spy/attach, POST /spy/detach, POST /spy/clear, POST /spy/event, \
+             POST /spy/subscribe, POST /spy/unsubscribe, GET /spy/status, GET /spy/calls, GET /spy/events",
             req.method(),
             req.path()
         ),
@@ -24,7 +27,35 @@ pub async fn not_found_handler(req: HttpRequest) -> HttpResponse {
 pub async fn logs_method_not_allowed(req: HttpRequest) -> HttpResponse {
     json_error(
         actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
-        &format!("Method {} is not allowed on /logs. Allowed: GET, DELETE", req.method()),
+        &format!("Method {} is not allowed on /logs. Allowed: GET, POST, DELETE", req.method()),
+    )
+}
+
+pub async fn logs_stream_method_not_allowed(req: HttpRequest) -> HttpResponse {
+    json_error(
+        actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
+        &format!("Method {} is not allowed on /logs/stream. Allowed: GET", req.method()),
+    )
+}
+
+pub async fn logs_tail_method_not_allowed(req: HttpRequest) -> HttpResponse {
+    json_error(
+        actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
+        &format!("Method {} is not allowed on /logs/tail. Allowed: GET", req.method()),
+    )
+}
+
+pub async fn logs_stats_method_not_allowed(req: HttpRequest) -> HttpResponse {
+    json_error(
+        actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
+        &format!("Method {} is not allowed on /logs/stats. Allowed: GET", req.method()),
+    )
+}
+
+pub async fn logs_export_method_not_allowed(req: HttpRequest) -> HttpResponse {
+    json_error(
+        actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
+        &format!("Method {} is not allowed on /logs/export. Allowed: POST", req.method()),
     )
 }
 
@@ -35,6 +66,27 @@ pub async fn clients_method_not_allowed(req: HttpRequest) -> HttpResponse {
     )
 }
 
+pub async fn clients_ws_method_not_allowed(req: HttpRequest) -> HttpResponse {
+    json_error(
+        actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
+        &format!("Method {} is not allowed on /clients/ws. Allowed: GET", req.method()),
+    )
+}
+
+pub async fn client_method_not_allowed(req: HttpRequest) -> HttpResponse {
+    json_error(
+        actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
+        &format!("Method {} is not allowed on /clients/{{pid}}. Allowed: GET", req.method()),
+    )
+}
+
+pub async fn client_logs_method_not_allowed(req: HttpRequest) -> HttpResponse {
+    json_error(
+        actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
+        &format!("Method {} is not allowed on /clients/{{pid}}/logs. Allowed: GET", req.method()),
+    )
+}
+
 pub async fn execute_method_not_allowed(req: HttpRequest) -> HttpResponse {
     json_error(
         actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
@@ -42,6 +94,20 @@ pub async fn execute_method_not_allowed(req: HttpRequest) -> HttpResponse {
     )
 }
 
+pub async fn execute_result_method_not_allowed(req: HttpRequest) -> HttpResponse {
+    json_error(
+        actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
+        &format!("Method {} is not allowed on /execute/{{id}}/result. Allowed: GET", req.method()),
+    )
+}
+
+pub async fn scripts_method_not_allowed(req: HttpRequest) -> HttpResponse {
+    json_error(
+        actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
+        &format!("Method {} is not allowed on /scripts. Allowed: GET, POST", req.method()),
+    )
+}
+
 pub async fn attach_logger_method_not_allowed(req: HttpRequest) -> HttpResponse {
     json_error(
         actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
@@ -49,6 +115,20 @@ pub async fn attach_logger_method_not_allowed(req: HttpRequest) -> HttpResponse
     )
 }
 
+pub async fn detach_logger_method_not_allowed(req: HttpRequest) -> HttpResponse {
+    json_error(
+        actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
+        &format!("Method {} is not allowed on /detach-logger. Allowed: POST", req.method()),
+    )
+}
+
+pub async fn heartbeat_method_not_allowed(req: HttpRequest) -> HttpResponse {
+    json_error(
+        actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
+        &format!("Method {} is not allowed on /heartbeat. Allowed: POST", req.method()),
+    )
+}
+
 pub async fn internal_method_not_allowed(req: HttpRequest) -> HttpResponse {
     json_error(
         actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
@@ -69,3 +149,80 @@ pub async fn loader_script_method_not_allowed(req: HttpRequest) -> HttpResponse
         &format!("Method {} is not allowed on /loader-script. Allowed: GET", req.method()),
     )
 }
+
+pub async fn metrics_method_not_allowed(req: HttpRequest) -> HttpResponse {
+    json_error(
+        actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
+        &format!("Method {} is not allowed on /metrics. Allowed: GET", req.method()),
+    )
+}
+
+pub async fn verify_script_method_not_allowed(req: HttpRequest) -> HttpResponse {
+    json_error(
+        actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
+        &format!("Method {} is not allowed on /verify-script. Allowed: POST", req.method()),
+    )
+}
+
+pub async fn spy_attach_method_not_allowed(req: HttpRequest) -> HttpResponse {
+    json_error(
+        actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
+        &format!("Method {} is not allowed on /spy/attach. Allowed: POST", req.method()),
+    )
+}
+
+pub async fn spy_event_method_not_allowed(req: HttpRequest) -> HttpResponse {
+    json_error(
+        actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
+        &format!("Method {} is not allowed on /spy/event. Allowed: POST", req.method()),
+    )
+}
+
+pub async fn spy_clear_method_not_allowed(req: HttpRequest) -> HttpResponse {
+    json_error(
+        actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
+        &format!("Method {} is not allowed on /spy/clear. Allowed: POST", req.method()),
+    )
+}
+
+pub async fn spy_detach_method_not_allowed(req: HttpRequest) -> HttpResponse {
+    json_error(
+        actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
+        &format!("Method {} is not allowed on /spy/detach. Allowed: POST", req.method()),
+    )
+}
+
+pub async fn spy_subscribe_method_not_allowed(req: HttpRequest) -> HttpResponse {
+    json_error(
+        actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
+        &format!("Method {} is not allowed on /spy/subscribe. Allowed: POST", req.method()),
+    )
+}
+
+pub async fn spy_unsubscribe_method_not_allowed(req: HttpRequest) -> HttpResponse {
+    json_error(
+        actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
+        &format!("Method {} is not allowed on /spy/unsubscribe. Allowed: POST", req.method()),
+    )
+}
+
+pub async fn spy_calls_method_not_allowed(req: HttpRequest) -> HttpResponse {
+    json_error(
+        actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
+        &format!("Method {} is not allowed on /spy/calls. Allowed: GET", req.method()),
+    )
+}
+
+pub async fn spy_events_method_not_allowed(req: HttpRequest) -> HttpResponse {
+    json_error(
+        actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
+        &format!("Method {} is not allowed on /spy/events. Allowed: GET", req.method()),
+    )
+}
+
+pub async fn spy_status_method_not_allowed(req: HttpRequest) -> HttpResponse {
+    json_error(
+        actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
+        &format!("Method {} is not allowed on /spy/status. Allowed: GET", req.method()),
+    )
+}
@@ -1,25 +1,33 @@
+mod config;
 mod errors;
 mod loader;
 mod logger;
 mod models;
+mod ratelimit;
 mod routes;
 mod spy;
 mod xeno;
 
-use actix_web::{web, web::JsonConfig, App, HttpResponse, HttpServer};
+use actix_cors::Cors;
+use actix_web::{middleware::{Compress, Condition}, web, web::{JsonConfig, PathConfig, QueryConfig}, App, HttpResponse, HttpServer};
 use chrono::Local;
-use clap::Parser;
-use parking_lot::RwLock;
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 use errors::*;
-use models::{AppState, Args, LogEntry, ServerMode};
-use routes::{health, internal, logs, spy as spy_routes, xeno as xeno_routes};
+use models::{AppState, LogEntry, ServerMode};
+use routes::{health, internal, logs, metrics, scripts, spy as spy_routes, xeno as xeno_routes};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    let args = Args::parse();
+    let args = match config::load_args() {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("xeno-mcp: {}", err);
+            std::process::exit(2);
+        }
+    };
     let bind_addr = format!("{}:{}", args.bind, args.port);
 
     let mode_str = match args.mode {
@@ -29,44 +37,149 @@ async fn main() -> std::io::Result<()> {
 
     println!("xeno-mcp listening on {}", bind_addr);
     println!("  mode: {}, console: {}, secret: {}", mode_str, args.console, args.secret.is_some());
+    if args.gate_reads && args.secret.is_none() {
+        println!("  warning: --gate-reads has no effect without --secret");
+    }
+    if let Some(max_age) = args.log_max_age_secs {
+        println!("  log retention: {}s", max_age);
+    }
     match args.mode {
         ServerMode::Xeno => println!("  xeno: {}", args.xeno_url),
         ServerMode::Generic => {
             println!("  exchange-dir: {}", args.exchange_dir);
             let pending = format!("{}/pending", args.exchange_dir);
             let done = format!("{}/done", args.exchange_dir);
+            let results = format!("{}/results", args.exchange_dir);
             std::fs::create_dir_all(&pending).expect("failed to create exchange/pending directory");
             std::fs::create_dir_all(&done).expect("failed to create exchange/done directory");
-            println!("  exchange dirs ready: pending/, done/");
+            std::fs::create_dir_all(&results).expect("failed to create exchange/results directory");
+            println!("  exchange dirs ready: pending/, done/, results/");
+        }
+    }
+
+    let mut initial_logs = VecDeque::with_capacity(args.max_entries);
+    if args.restore_logs {
+        match args.log_file {
+            Some(ref path) => match logs::load_log_file(path, args.max_entries) {
+                Ok((entries, skipped)) => {
+                    println!("  restored {} log entries from {}", entries.len(), path);
+                    if skipped > 0 {
+                        println!("  warning: skipped {} malformed line(s) in {}", skipped, path);
+                    }
+                    initial_logs.extend(entries);
+                }
+                Err(err) => println!("  warning: could not restore logs from {}: {}", path, err),
+            },
+            None => println!("  warning: --restore-logs has no effect without --log-file"),
         }
     }
+
     println!();
-    println!("  GET  /health         POST /internal");
+    println!("  GET  /health         POST /internal  POST /heartbeat");
     println!("  GET  /clients        POST /execute");
-    println!("  POST /attach-logger  GET  /loader-script");
-    println!("  GET  /logs           DEL  /logs");
+    println!("  GET  /clients/{{pid}}   GET  /clients/ws");
+    println!("  GET  /clients/{{pid}}/logs  GET /execute/{{id}}/result");
+    println!("  GET  /scripts        POST /scripts");
+    println!("  POST /attach-logger  POST /detach-logger");
+    println!("  GET  /loader-script");
+    println!("  GET  /logs           POST /logs");
+    println!("  DEL  /logs");
+    println!("  GET  /logs/stream    GET  /logs/stats");
+    println!("  POST /logs/export    GET  /logs/tail");
     println!("  POST /spy/attach     POST /spy/detach");
+    println!("  POST /spy/clear      POST /spy/event");
     println!("  POST /spy/subscribe  POST /spy/unsubscribe");
-    println!("  GET  /spy/status");
+    println!("  GET  /spy/status     GET  /metrics");
+    println!("  GET  /spy/calls      POST /verify-script");
+    println!("  GET  /spy/events");
     println!();
 
-    let state = Arc::new(AppState {
-        logs: RwLock::new(Vec::with_capacity(args.max_entries)),
-        logger_pids: RwLock::new(HashSet::new()),
-        generic_clients: RwLock::new(HashMap::new()),
-        spy_clients: RwLock::new(HashSet::new()),
-        spy_subscriptions: RwLock::new(HashMap::new()),
-        http_client: reqwest::Client::new(),
-        args: args.clone(),
-    });
+    let state = Arc::new(AppState::new(args.clone(), initial_logs));
 
-    // Background task: reap stale generic clients (no heartbeat for 15s)
+    // Background task: drop in-memory logs past --log-max-age-secs even when idle
+    if let Some(max_age) = args.log_max_age_secs {
+        let prune_state = state.clone();
+        tokio::spawn(async move {
+            let period = std::time::Duration::from_secs(max_age.clamp(1, 60));
+            loop {
+                tokio::time::sleep(period).await;
+                let dropped = logs::prune_expired(&mut prune_state.logs.write(), max_age);
+                prune_state.metrics.logs_evicted.fetch_add(dropped as u64, Ordering::Relaxed);
+            }
+        });
+    }
+
+    // Background task: log Xeno availability transitions (up <-> down) and, with
+    // --auto-reattach, re-send the logger to attached clients that lost it
+    if matches!(args.mode, ServerMode::Xeno) && (args.xeno_poll_secs.is_some() || args.auto_reattach) {
+        let monitor_state = state.clone();
+        tokio::spawn(async move {
+            let period = std::time::Duration::from_secs(monitor_state.args.xeno_poll_secs.unwrap_or(10).max(1));
+            loop {
+                let result = xeno::xeno_fetch_clients(&monitor_state, true).await;
+                if let (true, Ok(clients)) = (monitor_state.args.auto_reattach, &result) {
+                    let (message, level) = match xeno_routes::reattach_missing_loggers(&monitor_state, clients).await {
+                        Ok(pids) if pids.is_empty() => (None, "info"),
+                        Ok(pids) => (Some(format!("Re-sent the logger to {}", pids.join(", "))), "info"),
+                        Err(err) => (Some(format!("Logger re-attach failed: {}", err)), "warn"),
+                    };
+                    if let Some(message) = message {
+                        println!("[xeno-mcp] {}", message);
+                        logs::store_entry(&monitor_state, &LogEntry {
+                            id: uuid::Uuid::new_v4().to_string(),
+                            timestamp: Local::now(),
+                            level: level.to_string(),
+                            message,
+                            source: Some("xeno".to_string()),
+                            pid: None,
+                            username: None,
+                            tags: vec!["xeno".to_string(), "reattach".to_string()],
+                            repeat_count: 1,
+                            fields: HashMap::new(),
+                            data: None,
+                        });
+                    }
+                }
+                let up = result.is_ok();
+                let previous = monitor_state.xeno_up.lock().replace(up);
+                if monitor_state.args.xeno_poll_secs.is_some() && previous.is_some_and(|was_up| was_up != up) {
+                    let (message, tag) = match result {
+                        Ok(clients) => (format!("Xeno is reachable again ({} client(s))", clients.len()), "up"),
+                        Err(err) => (format!("Xeno became unreachable: {}", err), "down"),
+                    };
+                    println!("[xeno-mcp] {}", message);
+                    logs::store_entry(&monitor_state, &LogEntry {
+                        id: uuid::Uuid::new_v4().to_string(),
+                        timestamp: Local::now(),
+                        level: if up { "info" } else { "warn" }.to_string(),
+                        message,
+                        source: Some("xeno".to_string()),
+                        pid: None,
+                        username: None,
+                        tags: vec!["xeno".to_string(), tag.to_string()],
+                        repeat_count: 1,
+                        fields: HashMap::new(),
+                        data: None,
+                    });
+                }
+                tokio::time::sleep(period).await;
+            }
+        });
+    }
+
+    // Background task: diff the client list for GET /clients/ws subscribers
+    tokio::spawn(xeno_routes::watch_clients(state.clone()));
+
+    // Background task: reap stale generic clients (no heartbeat for --heartbeat-timeout-secs)
+    // and expired execute results (--result-timeout-secs)
     if matches!(args.mode, ServerMode::Generic) {
         let reaper_state = state.clone();
         tokio::spawn(async move {
-            let timeout_secs = 15;
+            let timeout_secs = reaper_state.args.heartbeat_timeout_secs as i64;
+            let period = std::time::Duration::from_secs(reaper_state.args.heartbeat_timeout_secs.clamp(1, 10));
             loop {
-                tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+                tokio::time::sleep(period).await;
+                xeno_routes::sweep_execute_results(&reaper_state);
                 let now = Local::now();
                 let mut clients = reaper_state.generic_clients.write();
                 for client in clients.values_mut() {
@@ -84,12 +197,11 @@ async fn main() -> std::io::Result<()> {
                                 pid: None,
                                 username: Some(client.username.clone()),
                                 tags: vec!["internal".to_string(), "disconnected".to_string(), "timeout".to_string(), "generic".to_string()],
+                                repeat_count: 1,
+                                fields: HashMap::new(),
+                                data: None,
                             };
-                            let mut logs = reaper_state.logs.write();
-                            if logs.len() >= reaper_state.args.max_entries {
-                                logs.remove(0);
-                            }
-                            logs.push(entry);
+                            logs::store_entry(&reaper_state, &entry);
                         }
                     }
                 }
@@ -97,6 +209,8 @@ async fn main() -> std::io::Result<()> {
         });
     }
 
+    let shutdown_state = state.clone();
+    let cors_origins = args.cors_origin.clone();
     HttpServer::new(move || {
         let json_cfg = JsonConfig::default()
             .limit(1024 * 1024)
@@ -128,30 +242,85 @@ async fn main() -> std::io::Result<()> {
                 }));
                 actix_web::error::InternalError::from_response(err, resp).into()
             });
+        // Malformed path segments and query strings get the same JSON envelope as other errors
+        let path_cfg = PathConfig::default().error_handler(|err, req| {
+            let resp = json_error(
+                actix_web::http::StatusCode::BAD_REQUEST,
+                &format!("Invalid path {}: {}", req.path(), err),
+            );
+            actix_web::error::InternalError::from_response(err, resp).into()
+        });
+        let query_cfg = QueryConfig::default().error_handler(|err, req| {
+            let resp = json_error(
+                actix_web::http::StatusCode::BAD_REQUEST,
+                &format!("Invalid query string for {}: {}", req.path(), err),
+            );
+            actix_web::error::InternalError::from_response(err, resp).into()
+        });
 
         App::new()
+            .wrap(Compress::default())
+            .wrap(Condition::new(!cors_origins.is_empty(), cors(&cors_origins)))
             .app_data(web::Data::new(state.clone()))
             .app_data(json_cfg)
+            .app_data(path_cfg)
+            .app_data(query_cfg)
             .service(
                 web::resource("/health")
                     .route(web::get().to(health::health))
                     .default_service(web::to(health_method_not_allowed)),
             )
+            .service(
+                web::resource("/metrics")
+                    .route(web::get().to(metrics::get_metrics))
+                    .default_service(web::to(metrics_method_not_allowed)),
+            )
             .service(
                 web::resource("/clients")
                     .route(web::get().to(xeno_routes::get_clients))
                     .default_service(web::to(clients_method_not_allowed)),
             )
+            .service(
+                web::resource("/clients/ws")
+                    .route(web::get().to(xeno_routes::clients_ws))
+                    .default_service(web::to(clients_ws_method_not_allowed)),
+            )
+            .service(
+                web::resource("/clients/{pid}")
+                    .route(web::get().to(xeno_routes::get_client))
+                    .default_service(web::to(client_method_not_allowed)),
+            )
+            .service(
+                web::resource("/clients/{pid}/logs")
+                    .route(web::get().to(xeno_routes::get_client_logs))
+                    .default_service(web::to(client_logs_method_not_allowed)),
+            )
             .service(
                 web::resource("/execute")
                     .route(web::post().to(xeno_routes::post_execute))
                     .default_service(web::to(execute_method_not_allowed)),
             )
+            .service(
+                web::resource("/execute/{file_id}/result")
+                    .route(web::get().to(xeno_routes::get_execute_result))
+                    .default_service(web::to(execute_result_method_not_allowed)),
+            )
+            .service(
+                web::resource("/scripts")
+                    .route(web::get().to(scripts::get_scripts))
+                    .route(web::post().to(scripts::post_save_script))
+                    .default_service(web::to(scripts_method_not_allowed)),
+            )
             .service(
                 web::resource("/attach-logger")
                     .route(web::post().to(xeno_routes::post_attach_logger))
                     .default_service(web::to(attach_logger_method_not_allowed)),
             )
+            .service(
+                web::resource("/detach-logger")
+                    .route(web::post().to(xeno_routes::post_detach_logger))
+                    .default_service(web::to(detach_logger_method_not_allowed)),
+            )
             .service(
                 web::resource("/loader-script")
                     .route(web::get().to(xeno_routes::get_loader_script))
@@ -160,6 +329,12 @@ async fn main() -> std::io::Result<()> {
             .service(
                 web::resource("/verify-script")
                     .route(web::post().to(xeno_routes::post_verify_script))
+                    .default_service(web::to(verify_script_method_not_allowed)),
+            )
+            .service(
+                web::resource("/heartbeat")
+                    .route(web::post().to(internal::post_heartbeat))
+                    .default_service(web::to(heartbeat_method_not_allowed)),
             )
             .service(
                 web::resource("/internal")
@@ -169,32 +344,120 @@ async fn main() -> std::io::Result<()> {
             .service(
                 web::resource("/logs")
                     .route(web::get().to(logs::get_logs))
+                    .route(web::post().to(logs::post_logs))
                     .route(web::delete().to(logs::delete_logs))
                     .default_service(web::to(logs_method_not_allowed)),
             )
+            .service(
+                web::resource("/logs/stream")
+                    .route(web::get().to(logs::stream_logs))
+                    .default_service(web::to(logs_stream_method_not_allowed)),
+            )
+            .service(
+                web::resource("/logs/export")
+                    .route(web::post().to(logs::post_export_logs))
+                    .default_service(web::to(logs_export_method_not_allowed)),
+            )
+            .service(
+                web::resource("/logs/stats")
+                    .route(web::get().to(logs::get_log_stats))
+                    .default_service(web::to(logs_stats_method_not_allowed)),
+            )
+            .service(
+                web::resource("/logs/tail")
+                    .route(web::get().to(logs::get_logs_tail))
+                    .default_service(web::to(logs_tail_method_not_allowed)),
+            )
             .service(
                 web::resource("/spy/attach")
                     .route(web::post().to(spy_routes::post_attach_spy))
+                    .default_service(web::to(spy_attach_method_not_allowed)),
             )
             .service(
                 web::resource("/spy/detach")
                     .route(web::post().to(spy_routes::post_detach_spy))
+                    .default_service(web::to(spy_detach_method_not_allowed)),
+            )
+            .service(
+                web::resource("/spy/clear")
+                    .route(web::post().to(spy_routes::post_spy_clear))
+                    .default_service(web::to(spy_clear_method_not_allowed)),
+            )
+            .service(
+                web::resource("/spy/event")
+                    .route(web::post().to(spy_routes::post_spy_event))
+                    .default_service(web::to(spy_event_method_not_allowed)),
             )
             .service(
                 web::resource("/spy/subscribe")
                     .route(web::post().to(spy_routes::post_spy_subscribe))
+                    .default_service(web::to(spy_subscribe_method_not_allowed)),
             )
             .service(
                 web::resource("/spy/unsubscribe")
                     .route(web::post().to(spy_routes::post_spy_unsubscribe))
+                    .default_service(web::to(spy_unsubscribe_method_not_allowed)),
             )
             .service(
                 web::resource("/spy/status")
                     .route(web::get().to(spy_routes::get_spy_status))
+                    .default_service(web::to(spy_status_method_not_allowed)),
+            )
+            .service(
+                web::resource("/spy/calls")
+                    .route(web::get().to(spy_routes::get_spy_calls))
+                    .default_service(web::to(spy_calls_method_not_allowed)),
+            )
+            .service(
+                web::resource("/spy/events")
+                    .route(web::get().to(spy_routes::get_spy_events))
+                    .default_service(web::to(spy_events_method_not_allowed)),
             )
             .default_service(web::to(not_found_handler))
     })
     .bind(&bind_addr)?
+    .shutdown_timeout(args.shutdown_timeout_secs)
     .run()
-    .await
+    .await?;
+
+    // actix has stopped accepting connections and drained in-flight requests
+    // (SIGINT/SIGTERM); record the shutdown and make sure the log file is on disk.
+    logs::store_entry(&shutdown_state, &LogEntry {
+        id: uuid::Uuid::new_v4().to_string(),
+        timestamp: Local::now(),
+        level: "info".to_string(),
+        message: "xeno-mcp shutting down".to_string(),
+        source: Some("xeno-mcp".to_string()),
+        pid: None,
+        username: None,
+        tags: vec!["internal".to_string(), "shutdown".to_string()],
+        repeat_count: 1,
+        fields: HashMap::new(),
+        data: None,
+    });
+    if let Some(ref path) = args.log_file {
+        let _guard = shutdown_state.log_file_lock.lock();
+        if let Ok(f) = std::fs::File::open(path) {
+            let _ = f.sync_all();
+        }
+    }
+    println!("xeno-mcp stopped");
+    Ok(())
+}
+
+/// CORS policy for --cors-origin. Preflight OPTIONS requests are answered here,
+/// before routing, so they never reach the per-route method-not-allowed handlers.
+fn cors(origins: &[String]) -> Cors {
+    let mut cors = Cors::default()
+        .allowed_methods(vec!["GET", "POST", "DELETE"])
+        .allowed_headers(vec!["Content-Type", "X-Xeno-Secret"])
+        .max_age(3600);
+    if origins.iter().any(|o| o == "*") {
+        cors = cors.allow_any_origin();
+    } else {
+        for origin in origins {
+            cors = cors.allowed_origin(origin);
+        }
+    }
+    cors
 }
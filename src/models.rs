@@ -1,10 +1,16 @@
 use chrono::{DateTime, Local};
 use clap::{Parser, ValueEnum};
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::AtomicU64;
+use std::time::Instant;
+use tokio::sync::{broadcast, Notify};
 
-#[derive(Debug, Clone, ValueEnum)]
+use crate::ratelimit::RateLimiter;
+
+#[derive(Debug, Clone, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ServerMode {
     Xeno,
     Generic,
@@ -13,6 +19,11 @@ pub enum ServerMode {
 #[derive(Parser, Debug, Clone)]
 #[command(name = "xeno-mcp", about = "Roblox log receiver + Xeno API wrapper")]
 pub struct Args {
+    /// Read defaults for any of these options from a TOML file; flags given on
+    /// the command line still win
+    #[arg(long)]
+    pub config: Option<String>,
+
     /// Port to listen on
     #[arg(short, long, default_value_t = 3111)]
     pub port: u16,
@@ -29,27 +40,146 @@ pub struct Args {
     #[arg(long)]
     pub log_file: Option<String>,
 
+    /// Rotate --log-file once it would grow past this many bytes
+    #[arg(long)]
+    pub log_file_max_bytes: Option<u64>,
+
+    /// Number of rotated log files to keep (<path>.1 is the newest)
+    #[arg(long, default_value_t = 3)]
+    pub log_file_keep: usize,
+
+    /// On startup, reload the last `max_entries` entries from --log-file into memory
+    #[arg(long, default_value_t = false)]
+    pub restore_logs: bool,
+
     /// Shared secret – if set, every POST/DELETE must send header
     /// `X-Xeno-Secret` matching this value. GET requests are not gated.
     #[arg(long)]
     pub secret: Option<String>,
 
+    /// Read the shared secret from this file (trailing whitespace is trimmed).
+    /// The `XENO_MCP_SECRET` environment variable is also honoured
+    #[arg(long)]
+    pub secret_file: Option<String>,
+
+    /// Also require `X-Xeno-Secret` on GET endpoints that expose logs or clients
+    #[arg(long, default_value_t = false)]
+    pub gate_reads: bool,
+
+    /// Allow browser requests from this origin (repeatable, or `*` for any).
+    /// CORS stays disabled when no origin is given
+    #[arg(long)]
+    pub cors_origin: Vec<String>,
+
     /// Maximum number of log entries kept in memory (oldest evicted first)
     #[arg(long, default_value_t = 10_000)]
     pub max_entries: usize,
 
+    /// Maximum number of captured spy calls kept for GET /spy/events, separate from the log buffer
+    #[arg(long, default_value_t = 5_000)]
+    pub spy_events_max: usize,
+
+    /// Drop in-memory log entries older than this many seconds (the log file is untouched)
+    #[arg(long, visible_alias = "log-ttl-secs")]
+    pub log_max_age_secs: Option<u64>,
+
+    /// Collapse a log line into the previous entry when message, level, source and
+    /// client (PID/username) repeat within this many milliseconds (bumps its `repeat_count`)
+    #[arg(long)]
+    pub dedup_window_ms: Option<u64>,
+
+    /// Collapse consecutive identical log lines however far apart they arrive
+    /// (like --dedup-window-ms without a time limit)
+    #[arg(long, default_value_t = false)]
+    pub dedup_logs: bool,
+
+    /// Max POST /execute dispatches per second for each PID (for all clients together in generic mode).
+    /// Fractions are allowed, e.g. 0.5 for one every two seconds
+    #[arg(long, conflicts_with = "execute_rate_per_min")]
+    pub execute_rate: Option<f64>,
+
+    /// Same limit as --execute-rate, expressed per minute
+    #[arg(long)]
+    pub execute_rate_per_min: Option<u32>,
+
+    /// Reject POST /execute scripts that mention any of these identifiers
+    /// (comma-separated, whole-word textual match — not a Lua parser)
+    #[arg(long, value_delimiter = ',')]
+    pub execute_denylist: Vec<String>,
+
+    /// Executes a PID may fire back-to-back before rate pacing kicks in
+    /// (defaults to the per-second rate rounded up, or the per-minute rate)
+    #[arg(long)]
+    pub execute_burst: Option<u32>,
+
     /// Xeno local API base URL (only used in xeno mode)
     #[arg(long, default_value = "http://localhost:3110")]
     pub xeno_url: String,
 
+    /// Path of Xeno's client-list endpoint, appended to --xeno-url
+    #[arg(long, default_value = "/o")]
+    pub xeno_clients_path: String,
+
+    /// Path of Xeno's execute endpoint, appended to --xeno-url
+    #[arg(long, default_value = "/o")]
+    pub xeno_execute_path: String,
+
+    /// Timeout for each request to the Xeno API, in milliseconds
+    #[arg(long, default_value_t = 5000)]
+    pub xeno_timeout_ms: u64,
+
+    /// Poll Xeno every N seconds and log when it goes down or comes back (xeno mode)
+    #[arg(long)]
+    pub xeno_poll_secs: Option<u64>,
+
+    /// How often GET /clients/ws subscribers' client list is re-checked for changes, in milliseconds
+    #[arg(long, default_value_t = 1000)]
+    pub clients_poll_ms: u64,
+
+    /// Retries for Xeno requests that fail to connect, plus 5xx answers when
+    /// listing clients (exponential backoff from 100ms)
+    #[arg(long, default_value_t = 2)]
+    pub xeno_retries: u32,
+
+    /// How long a fetched Xeno client list is reused, in milliseconds (0 disables caching)
+    #[arg(long, visible_alias = "client-cache-ms", default_value_t = 500)]
+    pub clients_cache_ms: u64,
+
     /// Server mode: "xeno" for Xeno WebSocket API, "generic" for file-based adapter
     #[arg(long, value_enum, default_value_t = ServerMode::Xeno)]
     pub mode: ServerMode,
 
+    /// Allow the remote spy in Xeno mode using a degraded, incoming-only template
+    #[arg(long, default_value_t = false)]
+    pub spy_allow_xeno: bool,
+
+    /// Re-send the logger to attached Xeno clients that no longer report one, checked
+    /// by the background monitor every --xeno-poll-secs (10s when unset). Clients
+    /// detached via POST /detach-logger are skipped
+    #[arg(long, default_value_t = false)]
+    pub auto_reattach: bool,
+
     /// Directory for script exchange files — real OS path where the server writes scripts (used in generic mode)
     #[arg(long, default_value = "./exchange")]
     pub exchange_dir: String,
 
+    /// Mark a generic client disconnected after this many seconds without a heartbeat
+    #[arg(long, visible_alias = "client-timeout-secs", default_value_t = 15)]
+    pub heartbeat_timeout_secs: u64,
+
+    /// How long a generic-mode execution may stay pending before
+    /// GET /execute/{id}/result gives up with 404
+    #[arg(long, default_value_t = 30)]
+    pub result_timeout_secs: u64,
+
+    /// On SIGINT/SIGTERM, wait this long for in-flight requests before closing them
+    #[arg(long, default_value_t = 30)]
+    pub shutdown_timeout_secs: u64,
+
+    /// Directory for server-side files such as log exports
+    #[arg(long, default_value = "./storage")]
+    pub storage_dir: String,
+
     /// Exchange directory path as seen by the executor's filesystem (used in the loader script).
     /// If not set, defaults to the same value as --exchange-dir.
     #[arg(long)]
@@ -67,21 +197,73 @@ pub struct LogEntry {
     pub username: Option<String>,
     #[serde(default)]
     pub tags: Vec<String>,
+    /// How many identical consecutive lines this entry stands for (see --dedup-logs / --dedup-window-ms)
+    #[serde(default = "default_repeat_count", skip_serializing_if = "is_single")]
+    pub repeat_count: u64,
+    /// Arbitrary structured metadata attached by the sending script
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub fields: HashMap<String, serde_json::Value>,
+    /// Structured (JSON) payload of the line; `message` stays the human-readable form
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+fn default_repeat_count() -> u64 {
+    1
+}
+
+fn is_single(count: &u64) -> bool {
+    *count == 1
 }
 
 #[derive(Debug, Deserialize)]
 pub struct LogQuery {
     pub level: Option<String>,
+    pub min_level: Option<String>,
     pub source: Option<String>,
     pub search: Option<String>,
+    pub search_mode: Option<String>,
     pub tag: Option<String>,
+    pub tag_mode: Option<String>,
+    pub tag_exclude: Option<String>,
     pub pid: Option<u64>,
     pub after: Option<String>,
     pub before: Option<String>,
     pub limit: Option<usize>,
     pub offset: Option<usize>,
     pub page: Option<usize>,
+    pub cursor: Option<String>,
     pub order: Option<String>,
+    pub format: Option<String>,
+    pub field: Option<String>,
+    pub data_path: Option<String>,
+    pub data_value: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClientsQuery {
+    /// Skip the --clients-cache-ms cache and ask Xeno directly
+    #[serde(default)]
+    pub fresh: bool,
+    /// Only clients in the Attached state (status 3); Xeno mode only
+    #[serde(default)]
+    pub attached_only: bool,
+    /// Only clients with (true) or without (false) a logger; Xeno mode only
+    pub with_logger: Option<bool>,
+    /// Case-insensitive substring of the username
+    pub username: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClientLogsQuery {
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LogTailQuery {
+    pub n: Option<usize>,
+    pub pid: Option<u64>,
+    pub level: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -95,15 +277,95 @@ pub struct XenoClient {
     pub logger_attached: bool,
 }
 
+/// What Xeno answered to a successful execute call.
+#[derive(Debug, Clone, Serialize)]
+pub struct XenoExecuteOutcome {
+    pub status: u16,
+    /// Time from sending the request (including connect retries) to reading the body
+    pub elapsed_ms: u64,
+    pub body: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ExecuteRequest {
+    #[serde(default)]
     pub script: String,
+    /// Base64-encoded script bytes, used when `script` is empty
+    pub script_b64: Option<String>,
+    /// Name of a script saved via POST /scripts, used when `script` and `script_b64` are empty
+    pub script_name: Option<String>,
     pub pids: Vec<String>,
+    /// Run on the PIDs that are known and attached instead of rejecting the whole request
+    #[serde(default)]
+    pub allow_partial: bool,
+    /// Validate targets and report what would happen without dispatching anything
+    #[serde(default)]
+    pub dry_run: bool,
+    /// After dispatch, wait up to this long for client output and return it inline
+    pub wait_ms: Option<u64>,
+}
+
+/// Result file the generic loader writes to `{exchange_dir}/results/{file_id}.json`
+/// after running a script. `status` is "done" or "error"; the server reports
+/// "pending" itself while the file doesn't exist yet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecuteResult {
+    pub file_id: String,
+    pub status: String,
+    #[serde(default)]
+    pub output: Vec<String>,
+    #[serde(default)]
+    pub returns: Vec<String>,
+    pub error: Option<String>,
+    /// Unix seconds, as reported by the executor's `os.time()`
+    pub finished_at: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SaveScriptRequest {
+    pub name: String,
+    pub script: String,
+}
+
+/// Body of POST /logs: a `LogEntry` whose `id` and `timestamp` may be omitted.
+#[derive(Debug, Deserialize)]
+pub struct IngestLogRequest {
+    pub id: Option<String>,
+    pub timestamp: Option<DateTime<Local>>,
+    pub level: String,
+    pub message: String,
+    pub source: Option<String>,
+    pub pid: Option<u64>,
+    pub username: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub fields: HashMap<String, serde_json::Value>,
+    pub data: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportLogsRequest {
+    /// Relative to `{storage_dir}/exports/`
+    pub path: String,
+    pub format: Option<String>,
+    /// Replace an existing export of the same name instead of failing with 409
+    #[serde(default)]
+    pub overwrite: bool,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct AttachLoggerRequest {
+    #[serde(default)]
     pub pids: Vec<String>,
+    /// Target every client currently in the Attached state instead of `pids`
+    #[serde(default)]
+    pub all: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HeartbeatRequest {
+    pub username: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -115,6 +377,47 @@ pub struct InternalEvent {
     pub source: Option<String>,
     #[serde(default)]
     pub tags: Vec<String>,
+    #[serde(default)]
+    pub fields: HashMap<String, serde_json::Value>,
+    pub data: Option<serde_json::Value>,
+    /// Traceback lines of an `error` event, stored under `data.stack`
+    pub stack: Option<Vec<String>>,
+    /// Batched form of a `log` event; replaces `level`/`message`/`source`/`tags`/`fields`.
+    pub entries: Option<Vec<InternalLogItem>>,
+}
+
+/// One line of a batched `log` event.
+#[derive(Debug, Deserialize)]
+pub struct InternalLogItem {
+    pub level: Option<String>,
+    pub message: String,
+    pub source: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub fields: HashMap<String, serde_json::Value>,
+    pub data: Option<serde_json::Value>,
+}
+
+/// One captured remote call, kept in its own buffer so ordinary log traffic
+/// can't evict it. `id` matches the log entry stored for the same call.
+#[derive(Debug, Clone, Serialize)]
+pub struct SpyEvent {
+    pub id: String,
+    pub timestamp: DateTime<Local>,
+    pub pid: Option<u64>,
+    pub username: Option<String>,
+    pub direction: String,
+    pub method: String,
+    pub path: String,
+    pub args: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SpyPathStats {
+    pub count: u64,
+    pub first_seen: DateTime<Local>,
+    pub last_seen: DateTime<Local>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -125,12 +428,75 @@ pub struct GenericClient {
     pub connected: bool,
 }
 
+/// Monotonic counters exposed on GET /metrics.
+#[derive(Default)]
+pub struct Metrics {
+    pub logs_stored: AtomicU64,
+    pub logs_evicted: AtomicU64,
+    pub executes_xeno: AtomicU64,
+    pub executes_generic: AtomicU64,
+}
+
 pub struct AppState {
-    pub logs: RwLock<Vec<LogEntry>>,
+    pub logs: RwLock<VecDeque<LogEntry>>,
     pub logger_pids: RwLock<HashSet<String>>,
+    /// PIDs whose logger was removed via POST /detach-logger; --auto-reattach leaves them alone.
+    /// When both are held, lock `logger_pids` first
+    pub logger_detached: RwLock<HashSet<String>>,
+    /// When the --auto-reattach monitor last re-sent the logger, and to which PIDs
+    pub last_reattach: Mutex<Option<(DateTime<Local>, Vec<String>)>>,
     pub generic_clients: RwLock<HashMap<String, GenericClient>>,
+    pub client_cache: RwLock<Option<(Instant, Vec<XenoClient>)>>,
+    pub pending_results: RwLock<HashMap<String, DateTime<Local>>>,
+    pub execute_limiter: RateLimiter,
+    /// Last Xeno reachability seen by the --xeno-poll-secs monitor (None until the first poll)
+    pub xeno_up: Mutex<Option<bool>>,
     pub spy_clients: RwLock<HashSet<String>>,
     pub spy_subscriptions: RwLock<HashMap<String, HashSet<String>>>,
+    /// Spy calls received per remote path since the last POST /spy/detach or /spy/clear
+    pub spy_call_counts: RwLock<HashMap<String, SpyPathStats>>,
+    /// Captured calls for GET /spy/events and /spy/calls, capped at --spy-events-max
+    pub spy_events: RwLock<VecDeque<SpyEvent>>,
+    pub log_tx: broadcast::Sender<LogEntry>,
+    /// Serialized client-list diffs for GET /clients/ws subscribers
+    pub clients_tx: broadcast::Sender<String>,
+    /// Poked by /internal attach/detach events so the client watcher re-polls immediately
+    pub clients_changed: Notify,
+    pub log_file_lock: Mutex<()>,
+    pub metrics: Metrics,
     pub http_client: reqwest::Client,
     pub args: Args,
 }
+
+impl AppState {
+    pub fn new(args: Args, initial_logs: VecDeque<LogEntry>) -> Self {
+        AppState {
+            logs: RwLock::new(initial_logs),
+            logger_pids: RwLock::new(HashSet::new()),
+            logger_detached: RwLock::new(HashSet::new()),
+            last_reattach: Mutex::new(None),
+            generic_clients: RwLock::new(HashMap::new()),
+            client_cache: RwLock::new(None),
+            pending_results: RwLock::new(HashMap::new()),
+            execute_limiter: match args.execute_rate {
+                Some(per_sec) => RateLimiter::per_second(Some(per_sec), args.execute_burst),
+                None => RateLimiter::per_minute(args.execute_rate_per_min, args.execute_burst),
+            },
+            xeno_up: Mutex::new(None),
+            spy_clients: RwLock::new(HashSet::new()),
+            spy_subscriptions: RwLock::new(HashMap::new()),
+            spy_call_counts: RwLock::new(HashMap::new()),
+            spy_events: RwLock::new(VecDeque::new()),
+            log_tx: broadcast::channel(1024).0,
+            clients_tx: broadcast::channel(64).0,
+            clients_changed: Notify::new(),
+            log_file_lock: Mutex::new(()),
+            metrics: Metrics::default(),
+            http_client: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_millis(args.xeno_timeout_ms))
+                .build()
+                .expect("failed to build HTTP client"),
+            args,
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Token bucket per key (PID in xeno mode, a single global key in generic mode).
+/// Each bucket holds up to `capacity` tokens and refills continuously at
+/// `per_sec` tokens per second. A limiter without a rate never rejects.
+pub struct RateLimiter {
+    per_sec: Option<f64>,
+    capacity: f64,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    /// `per_sec` tokens per second (--execute-rate); `burst` defaults to the
+    /// rate rounded up, and at least one.
+    pub fn per_second(per_sec: Option<f64>, burst: Option<u32>) -> Self {
+        // config::validate rejects non-positive rates; this only guards other callers
+        let per_sec = per_sec.filter(|r| r.is_finite() && *r > 0.0);
+        let default_burst = per_sec.map_or(1.0, |r| r.ceil());
+        Self::with_capacity(per_sec, burst, default_burst)
+    }
+
+    /// `per_min` tokens per minute (--execute-rate-per-min); `burst` defaults to `per_min`.
+    pub fn per_minute(per_min: Option<u32>, burst: Option<u32>) -> Self {
+        let per_min = per_min.filter(|n| *n > 0);
+        Self::with_capacity(per_min.map(|n| n as f64 / 60.0), burst, per_min.unwrap_or(1) as f64)
+    }
+
+    fn with_capacity(per_sec: Option<f64>, burst: Option<u32>, default_burst: f64) -> Self {
+        Self {
+            per_sec,
+            capacity: burst.filter(|n| *n > 0).map_or(default_burst, |n| n as f64),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Take one token from every key's bucket, or none at all if any is empty.
+    /// On rejection returns the seconds until the emptiest bucket refills one token.
+    pub fn try_acquire(&self, keys: &[String]) -> Result<(), f64> {
+        self.try_acquire_at(keys, Instant::now())
+    }
+
+    fn try_acquire_at(&self, keys: &[String], now: Instant) -> Result<(), f64> {
+        let Some(per_sec) = self.per_sec else {
+            return Ok(());
+        };
+        let capacity = self.capacity;
+
+        let mut buckets = self.buckets.lock();
+        // A bucket that has refilled completely is the same as a fresh one, so
+        // drop it; otherwise every PID ever seen would keep an entry forever.
+        buckets.retain(|_, b| {
+            b.tokens + now.saturating_duration_since(b.updated).as_secs_f64() * per_sec < capacity
+        });
+
+        let mut wait = 0.0_f64;
+        for key in keys {
+            let bucket = buckets.entry(key.clone()).or_insert(Bucket { tokens: capacity, updated: now });
+            let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+            bucket.tokens = (bucket.tokens + elapsed * per_sec).min(capacity);
+            bucket.updated = now;
+            if bucket.tokens < 1.0 {
+                wait = wait.max((1.0 - bucket.tokens) / per_sec);
+            }
+        }
+        if wait > 0.0 {
+            return Err(wait);
+        }
+        for key in keys {
+            if let Some(bucket) = buckets.get_mut(key) {
+                bucket.tokens -= 1.0;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn keys(k: &[&str]) -> Vec<String> {
+        k.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn burst_is_exhausted_then_rejected() {
+        let limiter = RateLimiter::per_minute(Some(60), Some(3));
+        let t0 = Instant::now();
+        for _ in 0..3 {
+            assert!(limiter.try_acquire_at(&keys(&["1"]), t0).is_ok());
+        }
+        let wait = limiter.try_acquire_at(&keys(&["1"]), t0).unwrap_err();
+        assert!((wait - 1.0).abs() < 1e-9, "60/min refills one token per second, got {wait}");
+    }
+
+    #[test]
+    fn refills_after_interval() {
+        let limiter = RateLimiter::per_minute(Some(60), Some(1));
+        let t0 = Instant::now();
+        assert!(limiter.try_acquire_at(&keys(&["1"]), t0).is_ok());
+        assert!(limiter.try_acquire_at(&keys(&["1"]), t0 + Duration::from_millis(500)).is_err());
+        assert!(limiter.try_acquire_at(&keys(&["1"]), t0 + Duration::from_millis(1000)).is_ok());
+        assert!(limiter.try_acquire_at(&keys(&["1"]), t0 + Duration::from_millis(1000)).is_err());
+    }
+
+    #[test]
+    fn keys_have_separate_buckets() {
+        let limiter = RateLimiter::per_minute(Some(60), Some(1));
+        let t0 = Instant::now();
+        assert!(limiter.try_acquire_at(&keys(&["1"]), t0).is_ok());
+        assert!(limiter.try_acquire_at(&keys(&["1"]), t0).is_err());
+        assert!(limiter.try_acquire_at(&keys(&["2"]), t0).is_ok());
+    }
+
+    #[test]
+    fn rejection_takes_no_tokens_from_other_keys() {
+        let limiter = RateLimiter::per_minute(Some(60), Some(1));
+        let t0 = Instant::now();
+        assert!(limiter.try_acquire_at(&keys(&["1"]), t0).is_ok());
+        assert!(limiter.try_acquire_at(&keys(&["1", "2"]), t0).is_err());
+        assert!(limiter.try_acquire_at(&keys(&["2"]), t0).is_ok());
+    }
+
+    #[test]
+    fn idle_buckets_are_evicted() {
+        let limiter = RateLimiter::per_minute(Some(60), Some(2));
+        let t0 = Instant::now();
+        for pid in 0..100 {
+            assert!(limiter.try_acquire_at(&keys(&[&pid.to_string()]), t0).is_ok());
+        }
+        assert_eq!(limiter.buckets.lock().len(), 100);
+        assert!(limiter.try_acquire_at(&keys(&["new"]), t0 + Duration::from_secs(1)).is_ok());
+        assert_eq!(limiter.buckets.lock().len(), 1);
+    }
+
+    #[test]
+    fn per_second_rate_refills_and_defaults_burst_to_rate() {
+        let limiter = RateLimiter::per_second(Some(2.0), None);
+        let t0 = Instant::now();
+        assert!(limiter.try_acquire_at(&keys(&["*"]), t0).is_ok());
+        assert!(limiter.try_acquire_at(&keys(&["*"]), t0).is_ok());
+        let wait = limiter.try_acquire_at(&keys(&["*"]), t0).unwrap_err();
+        assert!((wait - 0.5).abs() < 1e-9, "2/s refills one token every 0.5s, got {wait}");
+        assert!(limiter.try_acquire_at(&keys(&["*"]), t0 + Duration::from_millis(500)).is_ok());
+    }
+
+    #[test]
+    fn unlimited_never_rejects() {
+        let limiter = RateLimiter::per_minute(None, Some(1));
+        let t0 = Instant::now();
+        for _ in 0..1000 {
+            assert!(limiter.try_acquire_at(&keys(&["1"]), t0).is_ok());
+        }
+        assert!(limiter.buckets.lock().is_empty());
+    }
+}
@@ -1,11 +1,15 @@
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
 use std::collections::HashSet;
 use std::sync::Arc;
 
 use crate::models::{AppState, ServerMode};
+use crate::routes::logs::check_read_secret;
 use crate::xeno::xeno_fetch_clients;
 
-pub async fn health(state: web::Data<Arc<AppState>>) -> HttpResponse {
+/// Liveness and backend status. Stays open under --gate-reads, but without the
+/// secret the client list and logger PIDs are left out.
+pub async fn health(req: HttpRequest, state: web::Data<Arc<AppState>>) -> HttpResponse {
+    let redact = check_read_secret(&req, &state).is_err();
     let log_count = state.logs.read().len();
     let logger_pids_snapshot: Vec<String> =
         state.logger_pids.read().iter().cloned().collect();
@@ -17,7 +21,7 @@ pub async fn health(state: web::Data<Arc<AppState>>) -> HttpResponse {
 
     let backend_status = match state.args.mode {
         ServerMode::Xeno => {
-            match xeno_fetch_clients(&state).await {
+            match xeno_fetch_clients(&state, true).await {
                 Ok(clients) => {
                     {
                         let active_pids: HashSet<String> =
@@ -25,12 +29,19 @@ pub async fn health(state: web::Data<Arc<AppState>>) -> HttpResponse {
                         let mut lp = state.logger_pids.write();
                         lp.retain(|pid| active_pids.contains(pid));
                     }
-                    serde_json::json!({
+                    let mut status = serde_json::json!({
                         "connected": true,
                         "url": state.args.xeno_url,
                         "client_count": clients.len(),
                         "clients": clients,
-                    })
+                    });
+                    if state.args.auto_reattach {
+                        status["auto_reattach"] = match &*state.last_reattach.lock() {
+                            Some((at, pids)) => serde_json::json!({ "last_at": at, "last_pids": pids }),
+                            None => serde_json::json!({ "last_at": null, "last_pids": [] }),
+                        };
+                    }
+                    status
                 }
                 Err(err) => serde_json::json!({
                     "connected": false,
@@ -52,12 +63,21 @@ pub async fn health(state: web::Data<Arc<AppState>>) -> HttpResponse {
         }
     };
 
-    HttpResponse::Ok().json(serde_json::json!({
+    let mut body = serde_json::json!({
         "status": "ok",
         "server": "xeno-mcp",
         "mode": mode_str,
         "log_count": log_count,
         "logger_pids": logger_pids_snapshot,
         "xeno": backend_status,
-    }))
+    });
+    if redact {
+        if let Some(obj) = body.as_object_mut() {
+            obj.remove("logger_pids");
+        }
+        if let Some(xeno) = body["xeno"].as_object_mut() {
+            xeno.remove("clients");
+        }
+    }
+    HttpResponse::Ok().json(body)
 }
@@ -1,10 +1,12 @@
 use actix_web::{web, HttpRequest, HttpResponse};
 use chrono::Local;
+use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::models::{AppState, GenericClient, InternalEvent, LogEntry, ServerMode};
-use crate::routes::logs::{check_secret, store_entry};
+use crate::models::{AppState, GenericClient, HeartbeatRequest, InternalEvent, InternalLogItem, LogEntry, ServerMode};
+use crate::routes::logs::{check_secret, store_entries, store_entry};
+use crate::routes::spy::store_spy_event;
 use crate::xeno::xeno_fetch_clients;
 
 pub async fn post_internal(
@@ -28,10 +30,76 @@ pub async fn post_internal(
         }));
     }
 
-    match state.args.mode {
+    // Attach/detach changes what GET /clients/ws reports; wake its watcher now
+    let membership_changed = matches!(event.as_str(), "attached" | "disconnected");
+    let resp = match state.args.mode {
         ServerMode::Generic => handle_generic_event(event, username, evt, &state),
         ServerMode::Xeno => handle_xeno_event(event, username, evt, &state).await,
+    };
+    if membership_changed {
+        state.clients_changed.notify_one();
+    }
+    resp
+}
+
+/// Dedicated heartbeat route for generic-mode executors. Same effect as an
+/// `/internal` `heartbeat` event: registers the client if unknown, otherwise
+/// bumps `last_heartbeat` and marks it connected again.
+pub async fn post_heartbeat(
+    req: HttpRequest,
+    body: web::Json<HeartbeatRequest>,
+    state: web::Data<Arc<AppState>>,
+) -> HttpResponse {
+    if let Err(resp) = check_secret(&req, &state) {
+        return resp;
     }
+    if !matches!(state.args.mode, ServerMode::Generic) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "ok": false,
+            "error": "Heartbeats are only used in generic mode",
+            "status": 400
+        }));
+    }
+
+    let username = body.into_inner().username.trim().to_string();
+    if username.is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "ok": false,
+            "error": "username must not be empty",
+            "status": 400
+        }));
+    }
+
+    let client = touch_generic_client(&state, &username);
+    HttpResponse::Ok().json(serde_json::json!({
+        "ok": true,
+        "username": username,
+        "connected_at": client.connected_at.to_rfc3339(),
+        "last_heartbeat": client.last_heartbeat.to_rfc3339(),
+    }))
+}
+
+fn touch_generic_client(state: &web::Data<Arc<AppState>>, username: &str) -> GenericClient {
+    let mut clients = state.generic_clients.write();
+    let now = Local::now();
+    if let Some(client) = clients.get_mut(username) {
+        client.last_heartbeat = now;
+        if !client.connected {
+            client.connected = true;
+            println!("[xeno-mcp] \u{2713} Client '{}' reconnected via heartbeat", username);
+        }
+        return client.clone();
+    }
+    // Client not known (e.g. server restarted) — register it
+    let client = GenericClient {
+        username: username.to_string(),
+        last_heartbeat: now,
+        connected_at: now,
+        connected: true,
+    };
+    clients.insert(username.to_string(), client.clone());
+    println!("[xeno-mcp] \u{2713} Client '{}' registered via heartbeat", username);
+    client
 }
 
 fn handle_generic_event(
@@ -58,6 +126,9 @@ fn handle_generic_event(
                 pid: None,
                 username: Some(username.clone()),
                 tags: vec!["internal".to_string(), "attached".to_string(), "generic".to_string()],
+                repeat_count: 1,
+                fields: HashMap::new(),
+                data: None,
             };
             store_entry(state, &entry);
             println!("[xeno-mcp] \u{2713} Generic loader attached: {}", username);
@@ -81,24 +152,7 @@ fn handle_generic_event(
         }
 
         "heartbeat" => {
-            let mut clients = state.generic_clients.write();
-            let now = Local::now();
-            if let Some(client) = clients.get_mut(&username) {
-                client.last_heartbeat = now;
-                if !client.connected {
-                    client.connected = true;
-                    println!("[xeno-mcp] \u{2713} Client '{}' reconnected via heartbeat", username);
-                }
-            } else {
-                // Client not known (e.g. server restarted) — register it
-                clients.insert(username.clone(), GenericClient {
-                    username: username.clone(),
-                    last_heartbeat: now,
-                    connected_at: now,
-                    connected: true,
-                });
-                println!("[xeno-mcp] \u{2713} Client '{}' registered via heartbeat", username);
-            }
+            touch_generic_client(state, &username);
             HttpResponse::Ok().json(serde_json::json!({
                 "ok": true,
                 "event": "heartbeat",
@@ -124,6 +178,9 @@ fn handle_generic_event(
                 pid: None,
                 username: Some(username.clone()),
                 tags: vec!["internal".to_string(), "disconnected".to_string(), "generic".to_string()],
+                repeat_count: 1,
+                fields: HashMap::new(),
+                data: None,
             };
             store_entry(state, &entry);
 
@@ -140,6 +197,10 @@ fn handle_generic_event(
         }
 
         "log" => {
+            if let Some(items) = evt.entries {
+                touch_generic_client(state, &username);
+                return store_log_batch(state, items, &username, None);
+            }
             let message = match evt.message {
                 Some(m) if !m.is_empty() => m,
                 _ => {
@@ -179,6 +240,9 @@ fn handle_generic_event(
                 pid: None,
                 username: Some(username.clone()),
                 tags: if evt.tags.is_empty() { vec!["auto".into()] } else { evt.tags },
+                repeat_count: 1,
+                fields: evt.fields,
+                data: evt.data,
             };
             let id = entry.id.clone();
             store_entry(state, &entry);
@@ -190,6 +254,22 @@ fn handle_generic_event(
             }))
         }
 
+        "error" => {
+            let entry = match error_entry(evt, &username, None) {
+                Ok(e) => e,
+                Err(resp) => return resp,
+            };
+            touch_generic_client(state, &username);
+            let id = entry.id.clone();
+            store_entry(state, &entry);
+
+            HttpResponse::Ok().json(serde_json::json!({
+                "ok": true,
+                "event": "error",
+                "id": id,
+            }))
+        }
+
         "spy" => {
             let message = match evt.message {
                 Some(m) if !m.is_empty() => m,
@@ -215,8 +295,12 @@ fn handle_generic_event(
                 pid: None,
                 username: Some(username.clone()),
                 tags: if evt.tags.is_empty() { vec!["spy".into()] } else { evt.tags },
+                repeat_count: 1,
+                fields: HashMap::new(),
+                data: None,
             };
             let id = entry.id.clone();
+            store_spy_event(state, &entry);
             store_entry(state, &entry);
 
             HttpResponse::Ok().json(serde_json::json!({
@@ -254,24 +338,122 @@ fn handle_generic_event(
 
         _ => HttpResponse::BadRequest().json(serde_json::json!({
             "ok": false,
-            "error": format!("Unknown event '{}'. Valid events: attached, already_attached, heartbeat, disconnected, log, spy, spy_attached, spy_detached", event),
+            "error": format!("Unknown event '{}'. Valid events: attached, already_attached, heartbeat, disconnected, log, error, spy, spy_attached, spy_detached", event),
             "status": 400
         })),
     }
 }
 
-async fn handle_xeno_event(
-    event: String,
-    username: String,
-    evt: InternalEvent,
+/// `error` event: a runtime error whose (possibly multi-line) message is kept
+/// as-is, tagged so `/logs?level=error&tag=traceback` finds it.
+fn error_entry(evt: InternalEvent, username: &str, pid: Option<u64>) -> Result<LogEntry, HttpResponse> {
+    let message = match evt.message {
+        Some(m) if !m.trim().is_empty() => m,
+        _ => {
+            return Err(HttpResponse::BadRequest().json(serde_json::json!({
+                "ok": false,
+                "error": "error event requires a non-empty 'message' field",
+                "status": 400
+            })));
+        }
+    };
+    let data = match (evt.data, evt.stack) {
+        (data, None) => data,
+        (Some(serde_json::Value::Object(mut map)), Some(stack)) => {
+            map.insert("stack".to_string(), serde_json::json!(stack));
+            Some(serde_json::Value::Object(map))
+        }
+        (None, Some(stack)) => Some(serde_json::json!({ "stack": stack })),
+        (Some(other), Some(stack)) => Some(serde_json::json!({ "value": other, "stack": stack })),
+    };
+    let mut tags = vec!["error".to_string(), "traceback".to_string()];
+    tags.extend(evt.tags.into_iter().filter(|t| t != "error" && t != "traceback"));
+
+    Ok(LogEntry {
+        id: Uuid::new_v4().to_string(),
+        timestamp: Local::now(),
+        level: "error".to_string(),
+        message,
+        source: evt.source.or(Some("roblox".into())),
+        pid,
+        username: Some(username.to_string()),
+        tags,
+        repeat_count: 1,
+        fields: evt.fields,
+        data,
+    })
+}
+
+/// Batched `log` event: every item is validated before any is stored, then all
+/// are stored together. Responds with the created ids in request order.
+fn store_log_batch(
     state: &web::Data<Arc<AppState>>,
+    items: Vec<InternalLogItem>,
+    username: &str,
+    pid: Option<u64>,
 ) -> HttpResponse {
-    let resolved_pid = match xeno_fetch_clients(state).await {
+    if items.is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "ok": false,
+            "error": "entries array must not be empty",
+            "status": 400
+        }));
+    }
+    if let Some(index) = items.iter().position(|item| item.message.is_empty()) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "ok": false,
+            "error": format!("entries[{}] requires a non-empty 'message' field", index),
+            "status": 400
+        }));
+    }
+
+    let now = Local::now();
+    let entries: Vec<LogEntry> = items
+        .into_iter()
+        .map(|item| LogEntry {
+            id: Uuid::new_v4().to_string(),
+            timestamp: now,
+            level: item.level.unwrap_or_else(|| "output".into()),
+            message: item.message,
+            source: item.source.or(Some("roblox".into())),
+            pid,
+            username: Some(username.to_string()),
+            tags: if item.tags.is_empty() { vec!["auto".into()] } else { item.tags },
+            repeat_count: 1,
+            fields: item.fields,
+            data: item.data,
+        })
+        .collect();
+    let ids: Vec<&str> = entries.iter().map(|e| e.id.as_str()).collect();
+    let resp = HttpResponse::Ok().json(serde_json::json!({
+        "ok": true,
+        "event": "log",
+        "ids": ids,
+    }));
+    store_entries(state, &entries);
+    resp
+}
+
+async fn resolve_pid(state: &AppState, username: &str, force: bool) -> Option<String> {
+    match xeno_fetch_clients(state, force).await {
         Ok(clients) => clients
             .iter()
-            .find(|c| c.username.eq_ignore_ascii_case(&username))
+            .find(|c| c.username.eq_ignore_ascii_case(username))
             .map(|c| c.pid.to_string()),
         Err(_) => None,
+    }
+}
+
+async fn handle_xeno_event(
+    event: String,
+    username: String,
+    evt: InternalEvent,
+    state: &web::Data<Arc<AppState>>,
+) -> HttpResponse {
+    let resolved_pid = match resolve_pid(state, &username, false).await {
+        // A client that just joined may not be in the cached list yet
+        None => resolve_pid(state, &username, true).await,
+        found => found,
     };
 
     match event.as_str() {
@@ -288,6 +470,9 @@ async fn handle_xeno_event(
                 pid: resolved_pid.as_ref().and_then(|p| p.parse::<u64>().ok()),
                 username: Some(username.clone()),
                 tags: vec!["internal".to_string(), "attached".to_string()],
+                repeat_count: 1,
+                fields: HashMap::new(),
+                data: None,
             };
             store_entry(state, &entry);
             println!(
@@ -317,6 +502,9 @@ async fn handle_xeno_event(
                 pid: resolved_pid.as_ref().and_then(|p| p.parse::<u64>().ok()),
                 username: Some(username.clone()),
                 tags: vec!["internal".to_string(), "already_attached".to_string()],
+                repeat_count: 1,
+                fields: HashMap::new(),
+                data: None,
             };
             store_entry(state, &entry);
 
@@ -344,6 +532,9 @@ async fn handle_xeno_event(
                 pid: resolved_pid.as_ref().and_then(|p| p.parse::<u64>().ok()),
                 username: Some(username.clone()),
                 tags: vec!["internal".to_string(), "disconnected".to_string()],
+                repeat_count: 1,
+                fields: HashMap::new(),
+                data: None,
             };
             store_entry(state, &entry);
 
@@ -365,6 +556,13 @@ async fn handle_xeno_event(
         }
 
         "log" => {
+            if let Some(items) = evt.entries {
+                if let Some(ref pid) = resolved_pid {
+                    state.logger_pids.write().insert(pid.clone());
+                }
+                let pid = resolved_pid.as_ref().and_then(|p| p.parse::<u64>().ok());
+                return store_log_batch(state, items, &username, pid);
+            }
             let message = match evt.message {
                 Some(m) if !m.is_empty() => m,
                 _ => {
@@ -392,6 +590,9 @@ async fn handle_xeno_event(
                 pid: resolved_pid.as_ref().and_then(|p| p.parse::<u64>().ok()),
                 username: Some(username.clone()),
                 tags: if evt.tags.is_empty() { vec!["auto".into()] } else { evt.tags },
+                repeat_count: 1,
+                fields: evt.fields,
+                data: evt.data,
             };
             let id = entry.id.clone();
             store_entry(state, &entry);
@@ -403,6 +604,22 @@ async fn handle_xeno_event(
             }))
         }
 
+        "error" => {
+            let pid = resolved_pid.as_ref().and_then(|p| p.parse::<u64>().ok());
+            let entry = match error_entry(evt, &username, pid) {
+                Ok(e) => e,
+                Err(resp) => return resp,
+            };
+            let id = entry.id.clone();
+            store_entry(state, &entry);
+
+            HttpResponse::Ok().json(serde_json::json!({
+                "ok": true,
+                "event": "error",
+                "id": id,
+            }))
+        }
+
         "spy" => {
             let message = match evt.message {
                 Some(m) if !m.is_empty() => m,
@@ -423,9 +640,20 @@ async fn handle_xeno_event(
                 source: Some("remote_spy".to_string()),
                 pid: resolved_pid.as_ref().and_then(|p| p.parse::<u64>().ok()),
                 username: Some(username.clone()),
-                tags: if evt.tags.is_empty() { vec!["spy".into()] } else { evt.tags },
+                // Xeno has no UNC hooks, so spy output there always comes from the degraded template
+                tags: {
+                    let mut tags = if evt.tags.is_empty() { vec!["spy".to_string()] } else { evt.tags };
+                    if !tags.iter().any(|t| t == "degraded") {
+                        tags.push("degraded".to_string());
+                    }
+                    tags
+                },
+                repeat_count: 1,
+                fields: HashMap::new(),
+                data: None,
             };
             let id = entry.id.clone();
+            store_spy_event(state, &entry);
             store_entry(state, &entry);
 
             HttpResponse::Ok().json(serde_json::json!({
@@ -468,7 +696,7 @@ async fn handle_xeno_event(
 
         _ => HttpResponse::BadRequest().json(serde_json::json!({
             "ok": false,
-            "error": format!("Unknown event '{}'. Valid events: attached, already_attached, disconnected, log, spy, spy_attached, spy_detached", event),
+            "error": format!("Unknown event '{}'. Valid events: attached, already_attached, disconnected, log, error, spy, spy_attached, spy_detached", event),
             "status": 400
         })),
     }
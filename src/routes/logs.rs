@@ -1,11 +1,24 @@
-use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::{web, web::Bytes, HttpRequest, HttpResponse};
 use chrono::{DateTime, Local};
+use regex::{Regex, RegexBuilder};
+use std::collections::{BTreeMap, VecDeque};
 use std::fs::OpenOptions;
 use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::time::Duration;
+use subtle::ConstantTimeEq;
+use tokio::sync::broadcast::error::RecvError;
 
 use crate::errors::json_error;
-use crate::models::{AppState, LogEntry, LogQuery};
+use crate::models::{AppState, ExportLogsRequest, IngestLogRequest, LogEntry, LogQuery, LogTailQuery};
+
+/// Constant-time equality so secret and signature checks don't leak how many
+/// leading bytes matched. Slices of different length compare unequal.
+pub fn secure_eq(a: &[u8], b: &[u8]) -> bool {
+    a.ct_eq(b).into()
+}
 
 pub fn check_secret(req: &HttpRequest, state: &AppState) -> Result<(), HttpResponse> {
     if let Some(ref secret) = state.args.secret {
@@ -14,7 +27,7 @@ pub fn check_secret(req: &HttpRequest, state: &AppState) -> Result<(), HttpRespo
             .get("X-Xeno-Secret")
             .and_then(|v| v.to_str().ok())
             .unwrap_or("");
-        if provided != secret {
+        if !secure_eq(provided.as_bytes(), secret.as_bytes()) {
             return Err(json_error(
                 actix_web::http::StatusCode::UNAUTHORIZED,
                 "invalid or missing X-Xeno-Secret header",
@@ -24,139 +37,999 @@ pub fn check_secret(req: &HttpRequest, state: &AppState) -> Result<(), HttpRespo
     Ok(())
 }
 
+/// `check_secret` for read-only endpoints; a no-op unless --gate-reads is set.
+pub fn check_read_secret(req: &HttpRequest, state: &AppState) -> Result<(), HttpResponse> {
+    if state.args.gate_reads {
+        check_secret(req, state)?;
+    }
+    Ok(())
+}
+
+/// Shift `<path>.N` to `<path>.N+1` (dropping anything past `keep`) and move
+/// `path` to `<path>.1`. With `keep == 0` the file is simply truncated.
+fn rotate_log_file(path: &str, keep: usize) -> std::io::Result<()> {
+    if keep == 0 {
+        return std::fs::File::create(path).map(|_| ());
+    }
+    let _ = std::fs::remove_file(format!("{}.{}", path, keep));
+    for i in (1..keep).rev() {
+        let from = format!("{}.{}", path, i);
+        if std::path::Path::new(&from).exists() {
+            std::fs::rename(&from, format!("{}.{}", path, i + 1))?;
+        }
+    }
+    std::fs::rename(path, format!("{}.1", path))
+}
+
+/// Read the newest `max_entries` lines of a JSONL log file, oldest first.
+/// Returns the parsed entries and the number of malformed lines skipped.
+pub fn load_log_file(path: &str, max_entries: usize) -> std::io::Result<(Vec<LogEntry>, usize)> {
+    let content = std::fs::read_to_string(path)?;
+    let lines: Vec<&str> = content.lines().filter(|l| !l.trim().is_empty()).collect();
+    let start = lines.len().saturating_sub(max_entries);
+
+    let mut skipped = 0;
+    let mut entries: Vec<LogEntry> = lines[start..]
+        .iter()
+        .filter_map(|line| match serde_json::from_str::<LogEntry>(line) {
+            Ok(entry) => Some(entry),
+            Err(_) => {
+                skipped += 1;
+                None
+            }
+        })
+        .collect();
+    entries.sort_by_key(|e| e.timestamp);
+    Ok((entries, skipped))
+}
+
+/// Pop expired entries off the front only, so inserts stay O(1). Older entries
+/// sitting behind a newer one are left for `prune_expired`.
+fn prune_expired_front(logs: &mut VecDeque<LogEntry>, max_age_secs: u64) -> usize {
+    let cutoff = Local::now() - chrono::Duration::seconds(max_age_secs as i64);
+    let mut dropped = 0;
+    while logs.front().is_some_and(|e| e.timestamp < cutoff) {
+        logs.pop_front();
+        dropped += 1;
+    }
+    dropped
+}
+
+/// Remove entries older than `max_age_secs`. Returns how many were dropped.
+/// Scans the whole buffer: POST /logs and /spy/event accept client-supplied
+/// timestamps, so arrival order is not timestamp order. Run periodically by
+/// the retention task rather than on every insert.
+pub fn prune_expired(logs: &mut VecDeque<LogEntry>, max_age_secs: u64) -> usize {
+    let cutoff = Local::now() - chrono::Duration::seconds(max_age_secs as i64);
+    let before = logs.len();
+    logs.retain(|e| e.timestamp >= cutoff);
+    before - logs.len()
+}
+
+/// `window_ms` of `None` means consecutive duplicates fold regardless of age (--dedup-logs).
+fn is_repeat(last: &LogEntry, entry: &LogEntry, window_ms: Option<u64>) -> bool {
+    last.message == entry.message
+        && last.level == entry.level
+        && last.source == entry.source
+        && last.pid == entry.pid
+        && last.username == entry.username
+        && window_ms.is_none_or(|window_ms| {
+            entry
+                .timestamp
+                .signed_duration_since(last.timestamp)
+                .num_milliseconds()
+                <= window_ms as i64
+        })
+}
+
 pub fn store_entry(state: &AppState, entry: &LogEntry) {
+    store_entries(state, std::slice::from_ref(entry));
+}
+
+/// Store several entries under one acquisition of the log file and buffer locks.
+pub fn store_entries(state: &AppState, entries: &[LogEntry]) {
     if state.args.console {
-        let origin = match (&entry.username, &entry.pid) {
-            (Some(u), Some(p)) => format!("{}({})", u, p),
-            (Some(u), None) => u.clone(),
-            (None, Some(p)) => format!("PID:{}", p),
-            (None, None) => "-".to_string(),
-        };
-        println!(
-            "[{}] [{}] [{}] {} | {}",
-            entry.timestamp.format("%H:%M:%S%.3f"),
-            entry.level.to_uppercase(),
-            origin,
-            entry.source.as_deref().unwrap_or("-"),
-            entry.message
-        );
+        for entry in entries {
+            let origin = match (&entry.username, &entry.pid) {
+                (Some(u), Some(p)) => format!("{}({})", u, p),
+                (Some(u), None) => u.clone(),
+                (None, Some(p)) => format!("PID:{}", p),
+                (None, None) => "-".to_string(),
+            };
+            println!(
+                "[{}] [{}] [{}] {} | {}",
+                entry.timestamp.format("%H:%M:%S%.3f"),
+                entry.level.to_uppercase(),
+                origin,
+                entry.source.as_deref().unwrap_or("-"),
+                entry.message
+            );
+        }
     }
     if let Some(ref path) = state.args.log_file {
-        if let Ok(line) = serde_json::to_string(entry) {
+        let _guard = state.log_file_lock.lock();
+        for entry in entries {
+            let Ok(line) = serde_json::to_string(entry) else {
+                continue;
+            };
+            if let Some(max_bytes) = state.args.log_file_max_bytes {
+                let current = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+                if current > 0 && current + line.len() as u64 + 1 > max_bytes {
+                    if let Err(err) = rotate_log_file(path, state.args.log_file_keep) {
+                        println!("[xeno-mcp] warning: failed to rotate {}: {}", path, err);
+                    }
+                }
+            }
             if let Ok(mut f) = OpenOptions::new().create(true).append(true).open(path) {
                 let _ = writeln!(f, "{}", line);
             }
         }
     }
+    state.metrics.logs_stored.fetch_add(entries.len() as u64, Ordering::Relaxed);
     let mut logs = state.logs.write();
-    if logs.len() >= state.args.max_entries {
-        logs.remove(0);
+    if let Some(max_age) = state.args.log_max_age_secs {
+        let dropped = prune_expired_front(&mut logs, max_age);
+        state.metrics.logs_evicted.fetch_add(dropped as u64, Ordering::Relaxed);
+    }
+    let dedup = state.args.dedup_logs || state.args.dedup_window_ms.is_some();
+    for entry in entries {
+        if dedup {
+            if let Some(last) = logs.back_mut() {
+                if is_repeat(last, entry, state.args.dedup_window_ms) {
+                    last.repeat_count += 1;
+                    last.timestamp = entry.timestamp;
+                    let _ = state.log_tx.send(last.clone());
+                    continue;
+                }
+            }
+        }
+        if logs.len() >= state.args.max_entries && logs.pop_front().is_some() {
+            state.metrics.logs_evicted.fetch_add(1, Ordering::Relaxed);
+        }
+        logs.push_back(entry.clone());
+        // No subscribers is the common case; send only fails then.
+        let _ = state.log_tx.send(entry.clone());
     }
-    logs.push(entry.clone());
 }
 
-pub async fn get_logs(
-    query: web::Query<LogQuery>,
-    state: web::Data<Arc<AppState>>,
-) -> HttpResponse {
-    let logs = state.logs.read();
+/// Upper bound on compiled regex size for `search_mode=regex`.
+const SEARCH_REGEX_SIZE_LIMIT: usize = 1 << 20;
 
-    let after_dt = query.after.as_ref().and_then(|s| s.parse::<DateTime<Local>>().ok());
-    let before_dt = query.before.as_ref().and_then(|s| s.parse::<DateTime<Local>>().ok());
-    let tags: Vec<String> = query
-        .tag
-        .as_ref()
-        .map(|t| t.split(',').map(|s| s.trim().to_lowercase()).collect())
-        .unwrap_or_default();
+/// Severity order used by `min_level`, lowest first.
+const LEVEL_ORDER: [&str; 7] = ["trace", "debug", "info", "output", "script", "warn", "error"];
 
-    let mut filtered: Vec<&LogEntry> = logs
-        .iter()
-        .filter(|e| {
-            if let Some(ref lvl) = query.level {
-                if !e.level.eq_ignore_ascii_case(lvl) {
-                    return false;
-                }
-            }
-            if let Some(ref src) = query.source {
-                if !e
-                    .source
-                    .as_ref()
-                    .map(|s| s.to_lowercase().contains(&src.to_lowercase()))
-                    .unwrap_or(false)
-                {
-                    return false;
-                }
+/// Rank of a level on the `LEVEL_ORDER` scale. Known levels get even ranks so
+/// unknown ones can sit just below `warn` and aren't silently dropped.
+fn level_rank(level: &str) -> usize {
+    match LEVEL_ORDER.iter().position(|l| l.eq_ignore_ascii_case(level)) {
+        Some(i) => i * 2,
+        None => level_rank("warn") - 1,
+    }
+}
+
+enum SearchMatcher {
+    Substring(String),
+    Regex(Regex),
+}
+
+/// Filter criteria parsed once from a `LogQuery`, shared by `/logs` and `/logs/stream`.
+pub struct LogFilter {
+    level: Option<String>,
+    min_rank: Option<usize>,
+    source: Option<String>,
+    search: Option<SearchMatcher>,
+    pid: Option<u64>,
+    after: Option<DateTime<Local>>,
+    before: Option<DateTime<Local>>,
+    tags: Vec<String>,
+    tags_all: bool,
+    tags_exclude: Vec<String>,
+    field: Option<FieldFilter>,
+    data: Option<DataFilter>,
+}
+
+/// `data_path=a.b.0.c` with optional `data_value=`; without a value the path only has to exist.
+struct DataFilter {
+    path: Vec<String>,
+    value: Option<String>,
+    literal: Option<serde_json::Value>,
+}
+
+/// Follow a dotted path through objects (by key) and arrays (by index).
+fn walk_data<'a>(mut value: &'a serde_json::Value, path: &[String]) -> Option<&'a serde_json::Value> {
+    for segment in path {
+        value = match value {
+            serde_json::Value::Object(map) => map.get(segment)?,
+            serde_json::Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+/// `field=key:value`; `literal` is the value parsed as JSON, for non-string fields.
+struct FieldFilter {
+    key: String,
+    value: String,
+    literal: Option<serde_json::Value>,
+}
+
+/// Comma-separated tag list. A parameter that was sent but holds no tags
+/// (`tag=,`) is an error rather than "no filter".
+fn split_tags(name: &str, raw: &Option<String>) -> Result<Vec<String>, String> {
+    let Some(raw) = raw else { return Ok(Vec::new()) };
+    let tags: Vec<String> = raw
+        .split(',')
+        .map(|s| s.trim().to_lowercase())
+        .filter(|s| !s.is_empty())
+        .collect();
+    if tags.is_empty() {
+        return Err(format!("Invalid {} '{}'. Expected a comma-separated list of tags", name, raw));
+    }
+    Ok(tags)
+}
+
+fn parse_time(name: &str, raw: &Option<String>) -> Result<Option<DateTime<Local>>, String> {
+    raw.as_ref()
+        .map(|s| {
+            s.parse::<DateTime<Local>>()
+                .map_err(|_| format!("Invalid {} '{}'. Expected an ISO 8601 timestamp", name, s))
+        })
+        .transpose()
+}
+
+impl LogFilter {
+    pub fn from_query(query: &LogQuery) -> Result<Self, String> {
+        let search = match (query.search.as_ref(), query.search_mode.as_deref()) {
+            (None, _) => None,
+            (Some(s), None | Some("substring")) => Some(SearchMatcher::Substring(s.to_lowercase())),
+            (Some(s), Some("regex")) => {
+                let re = RegexBuilder::new(s)
+                    .size_limit(SEARCH_REGEX_SIZE_LIMIT)
+                    .build()
+                    .map_err(|e| format!("Invalid search regex: {}", e))?;
+                Some(SearchMatcher::Regex(re))
             }
-            if let Some(ref search) = query.search {
-                if !e.message.to_lowercase().contains(&search.to_lowercase()) {
-                    return false;
-                }
+            (Some(_), Some(other)) => {
+                return Err(format!(
+                    "Invalid search_mode '{}'. Expected: substring, regex",
+                    other
+                ));
             }
-            if let Some(pid) = query.pid {
-                if e.pid != Some(pid) {
-                    return false;
-                }
+        };
+
+        let min_rank = match query.min_level.as_deref() {
+            None => None,
+            Some(lvl) if LEVEL_ORDER.iter().any(|l| l.eq_ignore_ascii_case(lvl)) => Some(level_rank(lvl)),
+            Some(other) => {
+                return Err(format!(
+                    "Invalid min_level '{}'. Levels from lowest to highest: {}",
+                    other,
+                    LEVEL_ORDER.join(" < ")
+                ));
             }
-            if let Some(ref dt) = after_dt {
-                if e.timestamp < *dt {
-                    return false;
-                }
+        };
+
+        let tags_all = match query.tag_mode.as_deref() {
+            None | Some("any") => false,
+            Some("all") => true,
+            Some(other) => {
+                return Err(format!("Invalid tag_mode '{}'. Expected: any, all", other));
             }
-            if let Some(ref dt) = before_dt {
-                if e.timestamp > *dt {
-                    return false;
+        };
+
+        let field = match query.field.as_deref() {
+            None => None,
+            Some(raw) => match raw.split_once(':') {
+                Some((k, v)) if !k.is_empty() => Some(FieldFilter {
+                    key: k.to_string(),
+                    value: v.to_string(),
+                    literal: serde_json::from_str(v).ok(),
+                }),
+                _ => {
+                    return Err(format!("Invalid field filter '{}'. Expected: key:value", raw));
                 }
-            }
-            if !tags.is_empty() {
-                let entry_tags: Vec<String> =
-                    e.tags.iter().map(|t| t.to_lowercase()).collect();
-                if !tags.iter().any(|t| entry_tags.contains(t)) {
-                    return false;
+            },
+        };
+
+        let data = match (query.data_path.as_deref(), query.data_value.as_ref()) {
+            (None, None) => None,
+            (None, Some(_)) => return Err("data_value requires data_path".to_string()),
+            (Some(raw), value) => {
+                let path: Vec<String> = raw.split('.').map(str::to_string).collect();
+                if path.iter().any(|s| s.is_empty()) {
+                    return Err(format!("Invalid data_path '{}'. Expected dot-separated keys, e.g. a.b.0.c", raw));
                 }
+                Some(DataFilter {
+                    path,
+                    value: value.cloned(),
+                    literal: value.and_then(|v| serde_json::from_str(v).ok()),
+                })
             }
-            true
+        };
+
+        Ok(LogFilter {
+            level: query.level.clone(),
+            min_rank,
+            source: query.source.as_ref().map(|s| s.to_lowercase()),
+            search,
+            pid: query.pid,
+            after: parse_time("after", &query.after)?,
+            before: parse_time("before", &query.before)?,
+            tags: split_tags("tag", &query.tag)?,
+            tags_all,
+            tags_exclude: split_tags("tag_exclude", &query.tag_exclude)?,
+            field,
+            data,
         })
-        .collect();
+    }
+
+    /// True when no criteria were supplied, i.e. every entry matches.
+    pub fn is_empty(&self) -> bool {
+        self.level.is_none()
+            && self.min_rank.is_none()
+            && self.source.is_none()
+            && self.search.is_none()
+            && self.pid.is_none()
+            && self.after.is_none()
+            && self.before.is_none()
+            && self.tags.is_empty()
+            && self.tags_exclude.is_empty()
+            && self.field.is_none()
+            && self.data.is_none()
+    }
+
+    pub fn matches(&self, e: &LogEntry) -> bool {
+        if let Some(ref lvl) = self.level {
+            if !e.level.eq_ignore_ascii_case(lvl) {
+                return false;
+            }
+        }
+        if let Some(min_rank) = self.min_rank {
+            if level_rank(&e.level) < min_rank {
+                return false;
+            }
+        }
+        if let Some(ref src) = self.source {
+            if !e
+                .source
+                .as_ref()
+                .map(|s| s.to_lowercase().contains(src))
+                .unwrap_or(false)
+            {
+                return false;
+            }
+        }
+        let search_ok = match self.search {
+            Some(SearchMatcher::Substring(ref needle)) => e.message.to_lowercase().contains(needle),
+            Some(SearchMatcher::Regex(ref re)) => re.is_match(&e.message),
+            None => true,
+        };
+        if !search_ok {
+            return false;
+        }
+        if let Some(pid) = self.pid {
+            if e.pid != Some(pid) {
+                return false;
+            }
+        }
+        if let Some(ref dt) = self.after {
+            if e.timestamp < *dt {
+                return false;
+            }
+        }
+        if let Some(ref dt) = self.before {
+            if e.timestamp > *dt {
+                return false;
+            }
+        }
+        if !self.tags.is_empty() || !self.tags_exclude.is_empty() {
+            let entry_tags: Vec<String> =
+                e.tags.iter().map(|t| t.to_lowercase()).collect();
+            // Exclusion wins: an entry carrying any excluded tag is dropped
+            // even if it also satisfies the `tag` filter.
+            if self.tags_exclude.iter().any(|t| entry_tags.contains(t)) {
+                return false;
+            }
+            let included = if self.tags_all {
+                self.tags.iter().all(|t| entry_tags.contains(t))
+            } else {
+                self.tags.is_empty() || self.tags.iter().any(|t| entry_tags.contains(t))
+            };
+            if !included {
+                return false;
+            }
+        }
+        if let Some(ref f) = self.field {
+            // Strings compare by content, other JSON values by their literal form (e.g. 42, true).
+            let found = match e.fields.get(&f.key) {
+                Some(serde_json::Value::String(s)) => *s == f.value,
+                Some(other) => f.literal.as_ref() == Some(other),
+                None => false,
+            };
+            if !found {
+                return false;
+            }
+        }
+        if let Some(ref d) = self.data {
+            let found = match (e.data.as_ref().and_then(|v| walk_data(v, &d.path)), &d.value) {
+                (None, _) => false,
+                (Some(_), None) => true,
+                (Some(serde_json::Value::String(s)), Some(v)) => s == v,
+                (Some(other), Some(_)) => d.literal.as_ref() == Some(other),
+            };
+            if !found {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Serialization used by `GET /logs`; filters and paging apply to all of them.
+pub enum LogFormat {
+    Json,
+    Ndjson,
+    Csv,
+}
+
+impl LogFormat {
+    pub fn from_query(query: &LogQuery) -> Result<Self, String> {
+        Self::parse(query.format.as_deref())
+    }
+
+    pub fn parse(format: Option<&str>) -> Result<Self, String> {
+        match format {
+            None | Some("json") => Ok(LogFormat::Json),
+            Some("ndjson") | Some("jsonl") => Ok(LogFormat::Ndjson),
+            Some("csv") => Ok(LogFormat::Csv),
+            Some(other) => Err(format!(
+                "Invalid format '{}'. Expected: json, ndjson, jsonl, csv",
+                other
+            )),
+        }
+    }
+}
+
+/// Quote a CSV field per RFC 4180 when it contains a delimiter, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn render_ndjson(entries: &[&LogEntry]) -> String {
+    let mut out = String::new();
+    for e in entries {
+        if let Ok(line) = serde_json::to_string(e) {
+            out.push_str(&line);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+pub fn render_csv(entries: &[&LogEntry]) -> String {
+    let mut out = String::from("id,timestamp,level,source,pid,username,message,tags\r\n");
+    for e in entries {
+        let row = [
+            csv_field(&e.id),
+            csv_field(&e.timestamp.to_rfc3339()),
+            csv_field(&e.level),
+            csv_field(e.source.as_deref().unwrap_or("")),
+            e.pid.map(|p| p.to_string()).unwrap_or_default(),
+            csv_field(e.username.as_deref().unwrap_or("")),
+            csv_field(&e.message),
+            csv_field(&e.tags.join(",")),
+        ];
+        out.push_str(&row.join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+pub async fn get_logs(
+    req: HttpRequest,
+    query: web::Query<LogQuery>,
+    state: web::Data<Arc<AppState>>,
+) -> HttpResponse {
+    if let Err(resp) = check_read_secret(&req, &state) {
+        return resp;
+    }
+    let filter = match LogFilter::from_query(&query) {
+        Ok(f) => f,
+        Err(msg) => return json_error(actix_web::http::StatusCode::BAD_REQUEST, &msg),
+    };
+    let format = match LogFormat::from_query(&query) {
+        Ok(f) => f,
+        Err(msg) => return json_error(actix_web::http::StatusCode::BAD_REQUEST, &msg),
+    };
+    let logs = state.logs.read();
+
+    let mut filtered: Vec<&LogEntry> = logs.iter().filter(|e| filter.matches(e)).collect();
 
     let descending = query.order.as_ref().map(|o| o != "asc").unwrap_or(true);
     if descending {
-        filtered.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        filtered.sort_by_key(|e| std::cmp::Reverse(e.timestamp));
     } else {
-        filtered.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+        filtered.sort_by_key(|e| e.timestamp);
     }
 
     let total = filtered.len();
     let limit = query.limit.unwrap_or(50).min(1000);
-    let offset = if let Some(p) = query.page {
+    // A cursor resumes right after the entry with that id in the sorted, filtered
+    // list, so entries arriving between requests can't shift the window.
+    let offset = if let Some(ref cursor) = query.cursor {
+        match filtered.iter().position(|e| e.id == *cursor) {
+            Some(i) => i + 1,
+            None => {
+                return json_error(
+                    actix_web::http::StatusCode::BAD_REQUEST,
+                    &format!(
+                        "cursor '{}' not found; the entry may have been evicted or excluded by the current filters",
+                        cursor
+                    ),
+                );
+            }
+        }
+    } else if let Some(p) = query.page {
         let p = if p == 0 { 1 } else { p };
         (p - 1) * limit
     } else {
         query.offset.unwrap_or(0)
     };
-    let current_page = if limit > 0 { (offset / limit) + 1 } else { 1 };
-    let total_pages = if limit > 0 { (total + limit - 1) / limit } else { 1 };
+    let current_page = offset.checked_div(limit).map_or(1, |p| p + 1);
+    let total_pages = if limit > 0 { total.div_ceil(limit) } else { 1 };
     let page: Vec<&LogEntry> = filtered.into_iter().skip(offset).take(limit).collect();
     let has_more = offset + page.len() < total;
+    let next_cursor = if has_more { page.last().map(|e| e.id.clone()) } else { None };
 
-    HttpResponse::Ok().json(serde_json::json!({
+    match format {
+        LogFormat::Ndjson => {
+            return HttpResponse::Ok()
+                .content_type("application/x-ndjson")
+                .body(render_ndjson(&page));
+        }
+        LogFormat::Csv => {
+            return HttpResponse::Ok()
+                .content_type("text/csv; charset=utf-8")
+                .body(render_csv(&page));
+        }
+        LogFormat::Json => {}
+    }
+
+    let mut result = serde_json::json!({
         "total": total,
         "page": current_page,
         "per_page": limit,
         "total_pages": total_pages,
         "has_more": has_more,
+        "next_cursor": next_cursor,
         "logs": page
+    });
+    if query.cursor.is_none() && has_more {
+        result["hint"] = serde_json::json!(
+            "Pass next_cursor as 'cursor' to fetch the next page; offset/page can skip or repeat entries while new logs arrive."
+        );
+    }
+    HttpResponse::Ok().json(result)
+}
+
+/// Newest `n` entries (default 50, max 1000), read from the back of the buffer
+/// without the sort and paging of `get_logs`. Returned oldest first, like `tail`.
+pub async fn get_logs_tail(
+    req: HttpRequest,
+    query: web::Query<LogTailQuery>,
+    state: web::Data<Arc<AppState>>,
+) -> HttpResponse {
+    if let Err(resp) = check_read_secret(&req, &state) {
+        return resp;
+    }
+    let n = query.n.unwrap_or(50).min(1000);
+
+    let logs = state.logs.read();
+    let mut entries: Vec<&LogEntry> = logs
+        .iter()
+        .rev()
+        .filter(|e| query.pid.is_none() || e.pid == query.pid)
+        .filter(|e| query.level.as_ref().is_none_or(|lvl| e.level.eq_ignore_ascii_case(lvl)))
+        .take(n)
+        .collect();
+    entries.reverse();
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "ok": true,
+        "count": entries.len(),
+        "logs": entries,
     }))
 }
 
+/// Aggregate counts over the filtered log buffer without returning entries.
+pub async fn get_log_stats(
+    req: HttpRequest,
+    query: web::Query<LogQuery>,
+    state: web::Data<Arc<AppState>>,
+) -> HttpResponse {
+    if let Err(resp) = check_read_secret(&req, &state) {
+        return resp;
+    }
+    let filter = match LogFilter::from_query(&query) {
+        Ok(f) => f,
+        Err(msg) => return json_error(actix_web::http::StatusCode::BAD_REQUEST, &msg),
+    };
+    let logs = state.logs.read();
+
+    let mut total = 0usize;
+    let mut by_level: BTreeMap<String, usize> = BTreeMap::new();
+    let mut by_source: BTreeMap<String, usize> = BTreeMap::new();
+    let mut by_pid: BTreeMap<String, usize> = BTreeMap::new();
+    let mut oldest: Option<DateTime<Local>> = None;
+    let mut newest: Option<DateTime<Local>> = None;
+
+    for e in logs.iter().filter(|e| filter.matches(e)) {
+        total += 1;
+        *by_level.entry(e.level.to_lowercase()).or_default() += 1;
+        *by_source
+            .entry(e.source.clone().unwrap_or_else(|| "none".to_string()))
+            .or_default() += 1;
+        *by_pid
+            .entry(e.pid.map(|p| p.to_string()).unwrap_or_else(|| "none".to_string()))
+            .or_default() += 1;
+        oldest = Some(oldest.map_or(e.timestamp, |t| t.min(e.timestamp)));
+        newest = Some(newest.map_or(e.timestamp, |t| t.max(e.timestamp)));
+    }
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "ok": true,
+        "total": total,
+        "oldest": oldest,
+        "newest": newest,
+        "by_level": by_level,
+        "by_source": by_source,
+        "by_pid": by_pid,
+    }))
+}
+
+/// Store an entry sent by something other than a game client (CI, companion
+/// tools), so it shows up in the same timeline as captured output.
+pub async fn post_logs(
+    req: HttpRequest,
+    body: web::Json<IngestLogRequest>,
+    state: web::Data<Arc<AppState>>,
+) -> HttpResponse {
+    if let Err(resp) = check_secret(&req, &state) {
+        return resp;
+    }
+    let body = body.into_inner();
+    if body.level.trim().is_empty() {
+        return json_error(actix_web::http::StatusCode::BAD_REQUEST, "level must not be empty");
+    }
+    if body.message.is_empty() {
+        return json_error(actix_web::http::StatusCode::BAD_REQUEST, "message must not be empty");
+    }
+    let id = match body.id {
+        Some(id) if id.trim().is_empty() => {
+            return json_error(actix_web::http::StatusCode::BAD_REQUEST, "id must not be empty when given");
+        }
+        Some(id) => {
+            if state.logs.read().iter().any(|e| e.id == id) {
+                return json_error(
+                    actix_web::http::StatusCode::CONFLICT,
+                    &format!("A log entry with id '{}' already exists", id),
+                );
+            }
+            id
+        }
+        None => uuid::Uuid::new_v4().to_string(),
+    };
+
+    let entry = LogEntry {
+        id: id.clone(),
+        timestamp: body.timestamp.unwrap_or_else(Local::now),
+        level: body.level.trim().to_string(),
+        message: body.message,
+        source: body.source,
+        pid: body.pid,
+        username: body.username,
+        tags: body.tags,
+        repeat_count: 1,
+        fields: body.fields,
+        data: body.data,
+    };
+    store_entry(&state, &entry);
+
+    HttpResponse::Ok().json(serde_json::json!({ "ok": true, "id": id }))
+}
+
 pub async fn delete_logs(
     req: HttpRequest,
+    query: web::Query<LogQuery>,
     state: web::Data<Arc<AppState>>,
 ) -> HttpResponse {
     if let Err(resp) = check_secret(&req, &state) {
         return resp;
     }
+    let filter = match LogFilter::from_query(&query) {
+        Ok(f) => f,
+        Err(msg) => return json_error(actix_web::http::StatusCode::BAD_REQUEST, &msg),
+    };
+
+    // Malformed filters were rejected above, so an empty filter really means
+    // none were given (non-filter params such as `limit` don't count).
     let mut logs = state.logs.write();
-    let count = logs.len();
-    logs.clear();
-    HttpResponse::Ok().json(serde_json::json!({ "ok": true, "cleared": count }))
+    if filter.is_empty() {
+        let count = logs.len();
+        logs.clear();
+        return HttpResponse::Ok().json(serde_json::json!({ "ok": true, "cleared": count }));
+    }
+
+    let before = logs.len();
+    logs.retain(|e| !filter.matches(e));
+    HttpResponse::Ok().json(serde_json::json!({
+        "ok": true,
+        "cleared": before - logs.len(),
+        "remaining": logs.len(),
+    }))
+}
+
+/// Server-Sent Events feed of new log entries, filtered like `GET /logs`.
+pub async fn stream_logs(
+    req: HttpRequest,
+    query: web::Query<LogQuery>,
+    state: web::Data<Arc<AppState>>,
+) -> HttpResponse {
+    if let Err(resp) = check_read_secret(&req, &state) {
+        return resp;
+    }
+    let filter = match LogFilter::from_query(&query) {
+        Ok(f) => f,
+        Err(msg) => return json_error(actix_web::http::StatusCode::BAD_REQUEST, &msg),
+    };
+    let rx = state.log_tx.subscribe();
+    let heartbeat = tokio::time::interval_at(
+        tokio::time::Instant::now() + Duration::from_secs(15),
+        Duration::from_secs(15),
+    );
+
+    let stream = futures_util::stream::unfold(
+        (rx, heartbeat, filter),
+        |(mut rx, mut heartbeat, filter)| async move {
+            loop {
+                let frame = tokio::select! {
+                    _ = heartbeat.tick() => Bytes::from_static(b": heartbeat\n\n"),
+                    msg = rx.recv() => match msg {
+                        Ok(entry) => {
+                            if !filter.matches(&entry) {
+                                continue;
+                            }
+                            match serde_json::to_string(&entry) {
+                                Ok(json) => Bytes::from(format!("data: {}\n\n", json)),
+                                Err(_) => continue,
+                            }
+                        }
+                        Err(RecvError::Lagged(skipped)) => {
+                            Bytes::from(format!(": lagged, {} entries skipped\n\n", skipped))
+                        }
+                        Err(RecvError::Closed) => return None,
+                    },
+                };
+                return Some((Ok::<_, actix_web::Error>(frame), (rx, heartbeat, filter)));
+            }
+        },
+    );
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        // An explicit encoding keeps the Compress middleware from buffering frames
+        .insert_header(("Content-Encoding", "identity"))
+        .streaming(stream)
+}
+
+/// Resolve a client-supplied relative path beneath `base`, rejecting absolute
+/// paths and any `..` or root components.
+fn confined_path(base: &str, raw: &str) -> Option<PathBuf> {
+    let rel = Path::new(raw);
+    if raw.trim().is_empty() || !rel.components().all(|c| matches!(c, Component::Normal(_))) {
+        return None;
+    }
+    Some(Path::new(base).join(rel))
+}
+
+/// Write the filtered log buffer (oldest first) to a file under `{storage_dir}/exports/`,
+/// so exports can't clobber saved scripts or other server files.
+pub async fn post_export_logs(
+    req: HttpRequest,
+    query: web::Query<LogQuery>,
+    body: web::Json<ExportLogsRequest>,
+    state: web::Data<Arc<AppState>>,
+) -> HttpResponse {
+    if let Err(resp) = check_secret(&req, &state) {
+        return resp;
+    }
+    let filter = match LogFilter::from_query(&query) {
+        Ok(f) => f,
+        Err(msg) => return json_error(actix_web::http::StatusCode::BAD_REQUEST, &msg),
+    };
+    let req_body = body.into_inner();
+
+    let format = match LogFormat::parse(Some(req_body.format.as_deref().unwrap_or("jsonl"))) {
+        Ok(LogFormat::Json) => {
+            return json_error(
+                actix_web::http::StatusCode::BAD_REQUEST,
+                "Export format must be one of: jsonl, ndjson, csv",
+            );
+        }
+        Ok(f) => f,
+        Err(msg) => return json_error(actix_web::http::StatusCode::BAD_REQUEST, &msg),
+    };
+
+    let exports_dir = Path::new(&state.args.storage_dir).join("exports");
+    let target = match confined_path(&exports_dir.to_string_lossy(), &req_body.path) {
+        Some(p) => p,
+        None => {
+            return json_error(
+                actix_web::http::StatusCode::BAD_REQUEST,
+                "path must be a relative path inside the exports directory (no absolute paths or '..')",
+            );
+        }
+    };
+
+    let (content, count) = {
+        let logs = state.logs.read();
+        let mut entries: Vec<&LogEntry> = logs.iter().filter(|e| filter.matches(e)).collect();
+        entries.sort_by_key(|e| e.timestamp);
+        let rendered = match format {
+            LogFormat::Csv => render_csv(&entries),
+            _ => render_ndjson(&entries),
+        };
+        (rendered, entries.len())
+    };
+
+    if let Some(parent) = target.parent() {
+        if let Err(err) = std::fs::create_dir_all(parent) {
+            return json_error(
+                actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+                &format!("Failed to create export directory: {}", err),
+            );
+        }
+    }
+    let written = if req_body.overwrite {
+        std::fs::write(&target, &content)
+    } else {
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&target)
+            .and_then(|mut f| f.write_all(content.as_bytes()))
+    };
+    match written {
+        Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => json_error(
+            actix_web::http::StatusCode::CONFLICT,
+            &format!("{} already exists; pass \"overwrite\": true to replace it", target.to_string_lossy()),
+        ),
+        Ok(()) => HttpResponse::Ok().json(serde_json::json!({
+            "ok": true,
+            "path": target.to_string_lossy(),
+            "entries": count,
+            "bytes": content.len(),
+        })),
+        Err(err) => json_error(
+            actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Failed to write export file: {}", err),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+    use clap::Parser;
+
+    use crate::models::Args;
+
+    fn state_with_secret(secret: Option<&str>) -> AppState {
+        let mut args = Args::parse_from(["xeno-mcp"]);
+        args.secret = secret.map(str::to_string);
+        AppState::new(args, VecDeque::new())
+    }
+
+    fn state_with_capacity(max_entries: usize) -> AppState {
+        let mut args = Args::parse_from(["xeno-mcp"]);
+        args.max_entries = max_entries;
+        AppState::new(args, VecDeque::new())
+    }
+
+    fn entry(n: usize) -> LogEntry {
+        LogEntry {
+            id: n.to_string(),
+            timestamp: Local::now(),
+            level: "info".to_string(),
+            message: format!("message {}", n),
+            source: None,
+            pid: None,
+            username: None,
+            tags: Vec::new(),
+            repeat_count: 1,
+            fields: Default::default(),
+            data: None,
+        }
+    }
+
+    /// Push `total` entries one at a time and check that exactly the newest
+    /// `max_entries` survive, oldest first, and every eviction is counted.
+    fn assert_keeps_newest_window(max_entries: usize, total: usize) {
+        let state = state_with_capacity(max_entries);
+        for n in 0..total {
+            store_entry(&state, &entry(n));
+        }
+        let logs = state.logs.read();
+        assert_eq!(logs.len(), max_entries);
+        let ids: Vec<usize> = logs.iter().map(|e| e.id.parse().unwrap()).collect();
+        assert_eq!(ids, (total - max_entries..total).collect::<Vec<_>>());
+        assert_eq!(state.metrics.logs_evicted.load(Ordering::Relaxed), (total - max_entries) as u64);
+        assert_eq!(state.metrics.logs_stored.load(Ordering::Relaxed), total as u64);
+    }
+
+    #[test]
+    fn prune_expired_drops_old_entries_behind_a_fresh_head() {
+        let mut logs = VecDeque::new();
+        for (n, age_secs) in [(0, 0), (1, 120), (2, 5), (3, 300)] {
+            let mut e = entry(n);
+            e.timestamp = Local::now() - chrono::Duration::seconds(age_secs);
+            logs.push_back(e);
+        }
+        assert_eq!(prune_expired_front(&mut logs, 60), 0);
+        assert_eq!(prune_expired(&mut logs, 60), 2);
+        let ids: Vec<&str> = logs.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, ["0", "2"]);
+    }
+
+    #[test]
+    fn store_entries_evicts_oldest_first_at_100k() {
+        assert_keeps_newest_window(1000, 100_000);
+    }
+
+    #[test]
+    fn store_entries_keeps_newest_10k_window_of_100k() {
+        assert_keeps_newest_window(10_000, 100_000);
+    }
+
+    #[test]
+    fn secure_eq_compares_content_and_length() {
+        assert!(secure_eq(b"hunter2", b"hunter2"));
+        assert!(!secure_eq(b"hunter2", b"hunter3"));
+        assert!(!secure_eq(b"hunter2", b"hunter22"));
+        assert!(!secure_eq(b"", b"hunter2"));
+        assert!(secure_eq(b"", b""));
+    }
+
+    #[test]
+    fn check_secret_accepts_matching_header() {
+        let state = state_with_secret(Some("hunter2"));
+        let req = TestRequest::default().insert_header(("X-Xeno-Secret", "hunter2")).to_http_request();
+        assert!(check_secret(&req, &state).is_ok());
+    }
+
+    #[test]
+    fn check_secret_rejects_wrong_or_missing_header() {
+        let state = state_with_secret(Some("hunter2"));
+        for provided in [Some("hunter3"), Some("hunter"), None] {
+            let mut req = TestRequest::default();
+            if let Some(p) = provided {
+                req = req.insert_header(("X-Xeno-Secret", p));
+            }
+            let resp = check_secret(&req.to_http_request(), &state).unwrap_err();
+            assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+        }
+    }
+
+    #[test]
+    fn check_secret_is_open_without_a_configured_secret() {
+        let state = state_with_secret(None);
+        assert!(check_secret(&TestRequest::default().to_http_request(), &state).is_ok());
+    }
 }
@@ -0,0 +1,74 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use std::fmt::Write;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::models::AppState;
+use crate::routes::logs::check_read_secret;
+
+fn write_metric(out: &mut String, name: &str, kind: &str, help: &str, value: u64) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+pub async fn get_metrics(req: HttpRequest, state: web::Data<Arc<AppState>>) -> HttpResponse {
+    if let Err(resp) = check_read_secret(&req, &state) {
+        return resp;
+    }
+    let m = &state.metrics;
+    let mut out = String::new();
+
+    write_metric(
+        &mut out,
+        "xeno_mcp_logs_stored_total",
+        "counter",
+        "Log entries received by the server.",
+        m.logs_stored.load(Ordering::Relaxed),
+    );
+    write_metric(
+        &mut out,
+        "xeno_mcp_logs_evicted_total",
+        "counter",
+        "Log entries dropped from memory by --max-entries or --log-max-age-secs.",
+        m.logs_evicted.load(Ordering::Relaxed),
+    );
+    write_metric(
+        &mut out,
+        "xeno_mcp_logs_in_memory",
+        "gauge",
+        "Log entries currently held in memory.",
+        state.logs.read().len() as u64,
+    );
+    write_metric(
+        &mut out,
+        "xeno_mcp_logger_pids",
+        "gauge",
+        "Xeno PIDs with the logger attached.",
+        state.logger_pids.read().len() as u64,
+    );
+    write_metric(
+        &mut out,
+        "xeno_mcp_spy_clients",
+        "gauge",
+        "Clients with the remote spy attached.",
+        state.spy_clients.read().len() as u64,
+    );
+
+    let _ = writeln!(out, "# HELP xeno_mcp_executes_total Scripts dispatched via /execute.");
+    let _ = writeln!(out, "# TYPE xeno_mcp_executes_total counter");
+    let _ = writeln!(
+        out,
+        "xeno_mcp_executes_total{{mode=\"xeno\"}} {}",
+        m.executes_xeno.load(Ordering::Relaxed)
+    );
+    let _ = writeln!(
+        out,
+        "xeno_mcp_executes_total{{mode=\"generic\"}} {}",
+        m.executes_generic.load(Ordering::Relaxed)
+    );
+
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4; charset=utf-8")
+        .body(out)
+}
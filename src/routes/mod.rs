@@ -1,5 +1,7 @@
 pub mod health;
 pub mod internal;
 pub mod logs;
+pub mod metrics;
+pub mod scripts;
 pub mod spy;
 pub mod xeno;
@@ -0,0 +1,106 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::errors::json_error;
+use crate::models::{AppState, SaveScriptRequest};
+use crate::routes::logs::{check_read_secret, check_secret};
+
+/// `{storage_dir}/scripts/{name}.lua`, or an error when `name` could escape
+/// that directory.
+fn script_path(state: &AppState, name: &str) -> Result<PathBuf, String> {
+    let name = name.trim();
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\', '\0']) {
+        return Err(format!("Invalid script name '{}': must be non-empty and contain no path separators", name));
+    }
+    Ok(PathBuf::from(&state.args.storage_dir).join("scripts").join(format!("{}.lua", name)))
+}
+
+/// Source of a saved script, for POST /execute's `script_name`.
+pub fn load_script(state: &AppState, name: &str) -> Result<Vec<u8>, HttpResponse> {
+    let path = script_path(state, name)
+        .map_err(|msg| json_error(actix_web::http::StatusCode::BAD_REQUEST, &msg))?;
+    std::fs::read(&path).map_err(|err| match err.kind() {
+        std::io::ErrorKind::NotFound => json_error(
+            actix_web::http::StatusCode::NOT_FOUND,
+            &format!("No saved script named '{}'. Use GET /scripts to list them", name.trim()),
+        ),
+        _ => json_error(
+            actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Failed to read saved script '{}': {}", name.trim(), err),
+        ),
+    })
+}
+
+/// Save (or overwrite) a named script in the library.
+pub async fn post_save_script(
+    req: HttpRequest,
+    body: web::Json<SaveScriptRequest>,
+    state: web::Data<Arc<AppState>>,
+) -> HttpResponse {
+    if let Err(resp) = check_secret(&req, &state) {
+        return resp;
+    }
+    let req_body = body.into_inner();
+    let path = match script_path(&state, &req_body.name) {
+        Ok(p) => p,
+        Err(msg) => return json_error(actix_web::http::StatusCode::BAD_REQUEST, &msg),
+    };
+    if req_body.script.trim().is_empty() {
+        return json_error(actix_web::http::StatusCode::BAD_REQUEST, "script must not be empty");
+    }
+
+    let written = path.parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(&path, &req_body.script));
+    match written {
+        Ok(()) => HttpResponse::Ok().json(serde_json::json!({
+            "ok": true,
+            "name": req_body.name.trim(),
+            "bytes": req_body.script.len(),
+        })),
+        Err(err) => json_error(
+            actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Failed to save script: {}", err),
+        ),
+    }
+}
+
+/// List saved scripts by name with their size, sorted by name.
+pub async fn get_scripts(req: HttpRequest, state: web::Data<Arc<AppState>>) -> HttpResponse {
+    if let Err(resp) = check_read_secret(&req, &state) {
+        return resp;
+    }
+    let dir = PathBuf::from(&state.args.storage_dir).join("scripts");
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return HttpResponse::Ok().json(serde_json::json!({ "ok": true, "scripts": [] }));
+        }
+        Err(err) => {
+            return json_error(
+                actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+                &format!("Failed to list scripts: {}", err),
+            );
+        }
+    };
+
+    let mut scripts: Vec<(String, u64)> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let path = e.path();
+            if path.extension().and_then(|x| x.to_str()) != Some("lua") {
+                return None;
+            }
+            let name = path.file_stem()?.to_str()?.to_string();
+            let size = e.metadata().ok()?.len();
+            Some((name, size))
+        })
+        .collect();
+    scripts.sort();
+
+    let scripts: Vec<_> = scripts.into_iter()
+        .map(|(name, size)| serde_json::json!({ "name": name, "bytes": size }))
+        .collect();
+    HttpResponse::Ok().json(serde_json::json!({ "ok": true, "scripts": scripts }))
+}
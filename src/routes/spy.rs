@@ -1,16 +1,21 @@
 use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{DateTime, Local};
+use std::collections::HashMap;
 use std::sync::Arc;
 
-use crate::models::{AppState, ServerMode};
-use crate::routes::logs::check_secret;
-use crate::spy::build_spy_lua;
+use crate::errors::json_error;
+use crate::models::{AppState, LogEntry, ServerMode, SpyEvent, SpyPathStats};
+use crate::routes::logs::{check_read_secret, check_secret, store_entry};
+use crate::spy::{build_spy_lua, build_spy_xeno_lua, DEFAULT_MAX_ARG_DEPTH, MAX_ARG_DEPTH_LIMIT};
 use crate::xeno::xeno_execute;
 
-fn require_generic(state: &AppState) -> Result<(), HttpResponse> {
-    if matches!(state.args.mode, ServerMode::Xeno) {
+/// The spy needs UNC hooks, so Xeno mode is refused unless --spy-allow-xeno
+/// opts into the reduced incoming-only spy.
+fn require_spy_support(state: &AppState) -> Result<(), HttpResponse> {
+    if matches!(state.args.mode, ServerMode::Xeno) && !state.args.spy_allow_xeno {
         return Err(HttpResponse::BadRequest().json(serde_json::json!({
             "ok": false,
-            "error": "Remote spy requires UNC hook functions (hookfunction, hookmetamethod, newcclosure) which are not available in Xeno mode. Use generic mode with an executor that supports UNC.",
+            "error": "Remote spy requires UNC hook functions (hookfunction, hookmetamethod, newcclosure) which are not available in Xeno mode. Use generic mode with an executor that supports UNC, or start the server with --spy-allow-xeno for a degraded spy that only sees incoming OnClientEvent traffic.",
             "status": 400
         })));
     }
@@ -20,12 +25,72 @@ fn require_generic(state: &AppState) -> Result<(), HttpResponse> {
 #[derive(Debug, serde::Deserialize)]
 pub struct SpyRequest {
     pub pids: Option<Vec<String>>,
+    /// Arg table depth for unsubscribed remotes (attach only)
+    pub max_arg_depth: Option<u32>,
 }
 
 #[derive(Debug, serde::Deserialize)]
 pub struct SpySubscribeRequest {
-    pub path: String,
+    /// Single-path shorthand for `paths: [path]`.
+    pub path: Option<String>,
+    #[serde(default)]
+    pub paths: Vec<String>,
     pub pids: Option<Vec<String>>,
+    /// Arg table depth for calls on these paths (subscribe only)
+    pub max_arg_depth: Option<u32>,
+}
+
+/// `max_arg_depth` or the default, rejecting values above `MAX_ARG_DEPTH_LIMIT`.
+fn arg_depth(requested: Option<u32>) -> Result<u32, HttpResponse> {
+    match requested {
+        Some(depth) if depth > MAX_ARG_DEPTH_LIMIT => Err(HttpResponse::BadRequest().json(serde_json::json!({
+            "ok": false,
+            "error": format!("max_arg_depth must be at most {}", MAX_ARG_DEPTH_LIMIT),
+            "status": 400
+        }))),
+        Some(depth) => Ok(depth),
+        None => Ok(DEFAULT_MAX_ARG_DEPTH),
+    }
+}
+
+/// `path` and `paths` merged, trimmed and de-duplicated in request order.
+fn requested_paths(req_body: &SpySubscribeRequest) -> Result<Vec<String>, HttpResponse> {
+    let mut paths: Vec<String> = Vec::new();
+    for path in req_body.path.iter().chain(&req_body.paths) {
+        let path = path.trim();
+        if path.is_empty() {
+            return Err(HttpResponse::BadRequest().json(serde_json::json!({
+                "ok": false,
+                "error": "paths must not contain empty entries",
+                "status": 400
+            })));
+        }
+        if !paths.iter().any(|p| p == path) {
+            paths.push(path.to_string());
+        }
+    }
+    if paths.is_empty() {
+        return Err(HttpResponse::BadRequest().json(serde_json::json!({
+            "ok": false,
+            "error": "path or paths must be provided",
+            "status": 400
+        })));
+    }
+    Ok(paths)
+}
+
+/// Lua that calls `__XENO_SPY.<method>(path, extra)` once per path.
+fn spy_paths_lua(method: &str, paths: &[String], extra: Option<u32>) -> String {
+    let list = paths
+        .iter()
+        .map(|p| format!("\"{}\"", p.replace('\\', "\\\\").replace('"', "\\\"")))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let extra = extra.map(|v| format!(", {}", v)).unwrap_or_default();
+    format!(
+        r#"if getgenv().__XENO_SPY then for _, p in ipairs({{{}}}) do getgenv().__XENO_SPY.{}(p{}) end end"#,
+        list, method, extra
+    )
 }
 
 pub async fn post_attach_spy(
@@ -36,12 +101,19 @@ pub async fn post_attach_spy(
     if let Err(resp) = check_secret(&req, &state) {
         return resp;
     }
-    if let Err(resp) = require_generic(&state) {
+    if let Err(resp) = require_spy_support(&state) {
         return resp;
     }
 
-    let lua = build_spy_lua(state.args.port, &state.args.secret);
     let req_body = body.into_inner();
+    let max_arg_depth = match arg_depth(req_body.max_arg_depth) {
+        Ok(d) => d,
+        Err(resp) => return resp,
+    };
+    let lua = match state.args.mode {
+        ServerMode::Generic => build_spy_lua(state.args.port, &state.args.secret, max_arg_depth),
+        ServerMode::Xeno => build_spy_xeno_lua(state.args.port, &state.args.secret, max_arg_depth),
+    };
 
     match state.args.mode {
         ServerMode::Generic => {
@@ -69,7 +141,7 @@ pub async fn post_attach_spy(
             }
         }
         ServerMode::Xeno => {
-            // This shouldn't be reached due to require_generic, but handle gracefully
+            // Only reached with --spy-allow-xeno
             let pids = req_body.pids.unwrap_or_default();
             if pids.is_empty() {
                 return HttpResponse::BadRequest().json(serde_json::json!({
@@ -79,9 +151,9 @@ pub async fn post_attach_spy(
                 }));
             }
             match xeno_execute(&state, &lua, &pids).await {
-                Ok(()) => HttpResponse::Ok().json(serde_json::json!({
+                Ok(_) => HttpResponse::Ok().json(serde_json::json!({
                     "ok": true,
-                    "message": "Remote spy script sent",
+                    "message": "Degraded remote spy script sent (incoming OnClientEvent only; entries are tagged 'degraded')",
                     "sent_to": pids,
                 })),
                 Err(err) => HttpResponse::BadGateway().json(serde_json::json!({
@@ -102,7 +174,7 @@ pub async fn post_detach_spy(
     if let Err(resp) = check_secret(&req, &state) {
         return resp;
     }
-    if let Err(resp) = require_generic(&state) {
+    if let Err(resp) = require_spy_support(&state) {
         return resp;
     }
 
@@ -125,6 +197,7 @@ pub async fn post_detach_spy(
                     // Clear server-side spy state
                     state.spy_clients.write().clear();
                     state.spy_subscriptions.write().clear();
+                    state.spy_call_counts.write().clear();
 
                     HttpResponse::Ok().json(serde_json::json!({
                         "ok": true,
@@ -141,11 +214,12 @@ pub async fn post_detach_spy(
         ServerMode::Xeno => {
             let pids = req_body.pids.unwrap_or_default();
             match xeno_execute(&state, disconnect_lua, &pids).await {
-                Ok(()) => {
+                Ok(_) => {
                     let mut spy = state.spy_clients.write();
                     for pid in &pids { spy.remove(pid); }
                     let mut subs = state.spy_subscriptions.write();
                     for pid in &pids { subs.remove(pid); }
+                    state.spy_call_counts.write().clear();
 
                     HttpResponse::Ok().json(serde_json::json!({
                         "ok": true,
@@ -163,6 +237,36 @@ pub async fn post_detach_spy(
     }
 }
 
+/// Drop spy-captured calls from the log and spy-event buffers and reset the per-path counts,
+/// leaving the spy attached and its subscriptions in place.
+pub async fn post_spy_clear(
+    req: HttpRequest,
+    state: web::Data<Arc<AppState>>,
+) -> HttpResponse {
+    if let Err(resp) = check_secret(&req, &state) {
+        return resp;
+    }
+    let cleared = {
+        let mut logs = state.logs.write();
+        let before = logs.len();
+        logs.retain(|e| !e.tags.iter().any(|t| t == "spy"));
+        before - logs.len()
+    };
+    state.spy_call_counts.write().clear();
+    let events_cleared = {
+        let mut events = state.spy_events.write();
+        let n = events.len();
+        events.clear();
+        n
+    };
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "ok": true,
+        "cleared": cleared,
+        "events_cleared": events_cleared,
+    }))
+}
+
 pub async fn post_spy_subscribe(
     req: HttpRequest,
     body: web::Json<SpySubscribeRequest>,
@@ -171,24 +275,22 @@ pub async fn post_spy_subscribe(
     if let Err(resp) = check_secret(&req, &state) {
         return resp;
     }
-    if let Err(resp) = require_generic(&state) {
+    if let Err(resp) = require_spy_support(&state) {
         return resp;
     }
 
     let req_body = body.into_inner();
-    let path = req_body.path.trim().to_string();
-    if path.is_empty() {
-        return HttpResponse::BadRequest().json(serde_json::json!({
-            "ok": false,
-            "error": "path must not be empty",
-            "status": 400
-        }));
-    }
+    let paths = match requested_paths(&req_body) {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
+
+    let max_arg_depth = match arg_depth(req_body.max_arg_depth) {
+        Ok(d) => d,
+        Err(resp) => return resp,
+    };
 
-    let subscribe_lua = format!(
-        r#"if getgenv().__XENO_SPY then getgenv().__XENO_SPY.Subscribe("{}") end"#,
-        path.replace('\\', "\\\\").replace('"', "\\\"")
-    );
+    let subscribe_lua = spy_paths_lua("Subscribe", &paths, Some(max_arg_depth));
 
     match state.args.mode {
         ServerMode::Generic => {
@@ -207,12 +309,13 @@ pub async fn post_spy_subscribe(
                     state.spy_subscriptions.write()
                         .entry("generic".to_string())
                         .or_default()
-                        .insert(path.clone());
+                        .extend(paths.iter().cloned());
 
                     HttpResponse::Ok().json(serde_json::json!({
                         "ok": true,
-                        "message": format!("Subscribed to '{}' — all calls will now be logged", path),
-                        "path": path,
+                        "message": format!("Subscribed to {} path(s) — all calls will now be logged", paths.len()),
+                        "paths": paths,
+                        "max_arg_depth": max_arg_depth,
                     }))
                 }
                 Err(err) => HttpResponse::InternalServerError().json(serde_json::json!({
@@ -225,17 +328,18 @@ pub async fn post_spy_subscribe(
         ServerMode::Xeno => {
             let pids = req_body.pids.unwrap_or_default();
             match xeno_execute(&state, &subscribe_lua, &pids).await {
-                Ok(()) => {
-                    for pid in &pids {
-                        state.spy_subscriptions.write()
-                            .entry(pid.clone())
-                            .or_default()
-                            .insert(path.clone());
+                Ok(_) => {
+                    {
+                        let mut subs = state.spy_subscriptions.write();
+                        for pid in &pids {
+                            subs.entry(pid.clone()).or_default().extend(paths.iter().cloned());
+                        }
                     }
                     HttpResponse::Ok().json(serde_json::json!({
                         "ok": true,
-                        "message": format!("Subscribed to '{}'", path),
-                        "path": path,
+                        "message": format!("Subscribed to {} path(s)", paths.len()),
+                        "paths": paths,
+                        "max_arg_depth": max_arg_depth,
                         "sent_to": pids,
                     }))
                 }
@@ -257,24 +361,17 @@ pub async fn post_spy_unsubscribe(
     if let Err(resp) = check_secret(&req, &state) {
         return resp;
     }
-    if let Err(resp) = require_generic(&state) {
+    if let Err(resp) = require_spy_support(&state) {
         return resp;
     }
 
     let req_body = body.into_inner();
-    let path = req_body.path.trim().to_string();
-    if path.is_empty() {
-        return HttpResponse::BadRequest().json(serde_json::json!({
-            "ok": false,
-            "error": "path must not be empty",
-            "status": 400
-        }));
-    }
+    let paths = match requested_paths(&req_body) {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
 
-    let unsubscribe_lua = format!(
-        r#"if getgenv().__XENO_SPY then getgenv().__XENO_SPY.Unsubscribe("{}") end"#,
-        path.replace('\\', "\\\\").replace('"', "\\\"")
-    );
+    let unsubscribe_lua = spy_paths_lua("Unsubscribe", &paths, None);
 
     match state.args.mode {
         ServerMode::Generic => {
@@ -290,12 +387,14 @@ pub async fn post_spy_unsubscribe(
             match std::fs::write(&file_path, &file_content) {
                 Ok(()) => {
                     if let Some(subs) = state.spy_subscriptions.write().get_mut("generic") {
-                        subs.remove(&path);
+                        for path in &paths {
+                            subs.remove(path);
+                        }
                     }
                     HttpResponse::Ok().json(serde_json::json!({
                         "ok": true,
-                        "message": format!("Unsubscribed from '{}' — back to dedup-only", path),
-                        "path": path,
+                        "message": format!("Unsubscribed from {} path(s) — back to dedup-only", paths.len()),
+                        "paths": paths,
                     }))
                 }
                 Err(err) => HttpResponse::InternalServerError().json(serde_json::json!({
@@ -308,16 +407,21 @@ pub async fn post_spy_unsubscribe(
         ServerMode::Xeno => {
             let pids = req_body.pids.unwrap_or_default();
             match xeno_execute(&state, &unsubscribe_lua, &pids).await {
-                Ok(()) => {
-                    for pid in &pids {
-                        if let Some(subs) = state.spy_subscriptions.write().get_mut(pid) {
-                            subs.remove(&path);
+                Ok(_) => {
+                    {
+                        let mut subs = state.spy_subscriptions.write();
+                        for pid in &pids {
+                            if let Some(set) = subs.get_mut(pid) {
+                                for path in &paths {
+                                    set.remove(path);
+                                }
+                            }
                         }
                     }
                     HttpResponse::Ok().json(serde_json::json!({
                         "ok": true,
-                        "message": format!("Unsubscribed from '{}'", path),
-                        "path": path,
+                        "message": format!("Unsubscribed from {} path(s)", paths.len()),
+                        "paths": paths,
                         "sent_to": pids,
                     }))
                 }
@@ -332,8 +436,12 @@ pub async fn post_spy_unsubscribe(
 }
 
 pub async fn get_spy_status(
+    req: HttpRequest,
     state: web::Data<Arc<AppState>>,
 ) -> HttpResponse {
+    if let Err(resp) = check_read_secret(&req, &state) {
+        return resp;
+    }
     let clients: Vec<String> = state.spy_clients.read().iter().cloned().collect();
     let subs = state.spy_subscriptions.read();
     let subscriptions: serde_json::Value = subs.iter()
@@ -343,11 +451,253 @@ pub async fn get_spy_status(
         })
         .collect::<serde_json::Map<String, serde_json::Value>>()
         .into();
+    let counts = state.spy_call_counts.read();
+    let call_counts: serde_json::Map<String, serde_json::Value> = counts
+        .iter()
+        .map(|(path, stats)| (path.clone(), serde_json::json!(stats.count)))
+        .collect();
+    // Busiest remotes first
+    let mut seen: Vec<(&String, &SpyPathStats)> = counts.iter().collect();
+    seen.sort_by(|a, b| b.1.count.cmp(&a.1.count).then_with(|| a.0.cmp(b.0)));
+    let seen: Vec<_> = seen.into_iter()
+        .map(|(path, stats)| serde_json::json!({
+            "path": path,
+            "count": stats.count,
+            "first_seen": stats.first_seen.to_rfc3339(),
+            "last_seen": stats.last_seen.to_rfc3339(),
+        }))
+        .collect();
 
     HttpResponse::Ok().json(serde_json::json!({
         "ok": true,
         "active": !clients.is_empty(),
         "clients": clients,
         "subscriptions": subscriptions,
+        "call_counts": call_counts,
+        "unique_remotes": seen.len(),
+        "seen": seen,
     }))
 }
+
+#[derive(Debug, serde::Deserialize)]
+pub struct SpyCallsQuery {
+    /// Substring of the remote path, matched like spy subscriptions.
+    pub path: Option<String>,
+    /// Id of the last call already seen; only newer calls are returned.
+    pub since: Option<String>,
+    /// Only calls at or after this RFC 3339 timestamp, like `/logs?after=`.
+    pub after: Option<String>,
+    pub pid: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+/// A captured call posted directly by a client or companion tool.
+#[derive(Debug, serde::Deserialize)]
+pub struct SpyEventRequest {
+    pub path: String,
+    pub pid: Option<u64>,
+    pub username: Option<String>,
+    /// "in" or "out"
+    pub direction: Option<String>,
+    /// e.g. FireServer, InvokeServer, OnClientEvent
+    pub method: Option<String>,
+    #[serde(default)]
+    pub args: Vec<serde_json::Value>,
+    pub timestamp: Option<DateTime<Local>>,
+}
+
+/// Count one call on `path` for `/spy/status`.
+fn record_spy_call(state: &AppState, path: &str, at: DateTime<Local>) {
+    let mut counts = state.spy_call_counts.write();
+    let stats = counts.entry(path.to_string()).or_insert(SpyPathStats {
+        count: 0,
+        first_seen: at,
+        last_seen: at,
+    });
+    stats.count += 1;
+    stats.first_seen = stats.first_seen.min(at);
+    stats.last_seen = stats.last_seen.max(at);
+}
+
+/// Record a spy log entry in the spy-event buffer and the per-path counts.
+/// Entries whose message isn't in spy form are ignored.
+pub fn store_spy_event(state: &AppState, entry: &LogEntry) {
+    let Some((direction, method, path, args)) = parse_spy_message(&entry.message) else {
+        return;
+    };
+    record_spy_call(state, path, entry.timestamp);
+    let mut events = state.spy_events.write();
+    while events.len() >= state.args.spy_events_max.max(1) {
+        events.pop_front();
+    }
+    events.push_back(SpyEvent {
+        id: entry.id.clone(),
+        timestamp: entry.timestamp,
+        pid: entry.pid,
+        username: entry.username.clone(),
+        direction: direction.to_lowercase(),
+        method: method.to_string(),
+        path: path.to_string(),
+        args: args.to_string(),
+    });
+}
+
+/// Split a spy message `[DIR] [Method] Path(args)` into its parts.
+pub fn parse_spy_message(message: &str) -> Option<(&str, &str, &str, &str)> {
+    let rest = message.strip_prefix('[')?;
+    let (direction, rest) = rest.split_once("] [")?;
+    let (method, rest) = rest.split_once("] ")?;
+    let (path, args) = rest.split_once('(')?;
+    Some((direction, method, path, args.strip_suffix(')').unwrap_or(args)))
+}
+
+/// A page of the spy-event buffer, oldest first. Without `since` the newest
+/// `limit` events are returned; with it, the first `limit` events after that id.
+fn spy_event_page(state: &AppState, query: &SpyCallsQuery) -> Result<(Vec<SpyEvent>, bool, Option<String>), HttpResponse> {
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    let path_filter = query.path.as_deref().map(str::trim).filter(|p| !p.is_empty());
+    let after = match query.after.as_deref().map(str::parse::<DateTime<Local>>) {
+        Some(Err(_)) => {
+            return Err(json_error(actix_web::http::StatusCode::BAD_REQUEST, "after must be an RFC 3339 timestamp"));
+        }
+        Some(Ok(dt)) => Some(dt),
+        None => None,
+    };
+
+    let events = state.spy_events.read();
+    let matching: Vec<&SpyEvent> = events
+        .iter()
+        .filter(|e| query.pid.is_none() || e.pid == query.pid)
+        .filter(|e| after.is_none_or(|dt| e.timestamp >= dt))
+        .filter(|e| path_filter.is_none_or(|p| e.path.contains(p)))
+        .collect();
+
+    let start = match query.since {
+        Some(ref since) => match matching.iter().position(|e| e.id == *since) {
+            Some(i) => i + 1,
+            None => {
+                return Err(json_error(
+                    actix_web::http::StatusCode::BAD_REQUEST,
+                    &format!(
+                        "since '{}' not found; the call may have been evicted or excluded by the current filters",
+                        since
+                    ),
+                ));
+            }
+        },
+        None => matching.len().saturating_sub(limit),
+    };
+    let page: Vec<SpyEvent> = matching[start..matching.len().min(start + limit)]
+        .iter()
+        .map(|e| (*e).clone())
+        .collect();
+    let has_more = start + page.len() < matching.len();
+    let cursor = page.last().map(|e| e.id.clone()).or_else(|| query.since.clone());
+    Ok((page, has_more, cursor))
+}
+
+/// Captured spy events from the dedicated buffer (--spy-events-max), filtered
+/// by `path`, `pid` and `after` like `/logs`.
+pub async fn get_spy_events(
+    req: HttpRequest,
+    query: web::Query<SpyCallsQuery>,
+    state: web::Data<Arc<AppState>>,
+) -> HttpResponse {
+    if let Err(resp) = check_read_secret(&req, &state) {
+        return resp;
+    }
+    match spy_event_page(&state, &query) {
+        Ok((events, has_more, cursor)) => HttpResponse::Ok().json(serde_json::json!({
+            "ok": true,
+            "events": events,
+            "count": events.len(),
+            "has_more": has_more,
+            "cursor": cursor,
+        })),
+        Err(resp) => resp,
+    }
+}
+
+/// Same buffer as `/spy/events`, under the `calls` key used by `get_spy_calls`.
+pub async fn get_spy_calls(
+    req: HttpRequest,
+    query: web::Query<SpyCallsQuery>,
+    state: web::Data<Arc<AppState>>,
+) -> HttpResponse {
+    if let Err(resp) = check_read_secret(&req, &state) {
+        return resp;
+    }
+    match spy_event_page(&state, &query) {
+        Ok((calls, has_more, cursor)) => HttpResponse::Ok().json(serde_json::json!({
+            "ok": true,
+            "calls": calls,
+            "count": calls.len(),
+            "has_more": has_more,
+            "cursor": cursor,
+        })),
+        Err(resp) => resp,
+    }
+}
+
+/// Store one captured call sent outside the `/internal` spy event, in the same
+/// form so it shows up in `/spy/calls`, `/spy/status` counts and `/logs`.
+pub async fn post_spy_event(
+    req: HttpRequest,
+    body: web::Json<SpyEventRequest>,
+    state: web::Data<Arc<AppState>>,
+) -> HttpResponse {
+    if let Err(resp) = check_secret(&req, &state) {
+        return resp;
+    }
+    let body = body.into_inner();
+    let path = body.path.trim();
+    if path.is_empty() || path.contains('(') {
+        return json_error(
+            actix_web::http::StatusCode::BAD_REQUEST,
+            "path must be non-empty and must not contain '('",
+        );
+    }
+    let direction = match body.direction.as_deref().map(str::to_lowercase).as_deref() {
+        None | Some("out") => "OUT",
+        Some("in") => "IN",
+        Some(other) => {
+            return json_error(
+                actix_web::http::StatusCode::BAD_REQUEST,
+                &format!("Invalid direction '{}'. Expected: in, out", other),
+            );
+        }
+    };
+    let method = body.method.as_deref().map(str::trim).filter(|m| !m.is_empty()).unwrap_or(match direction {
+        "IN" => "OnClientEvent",
+        _ => "FireServer",
+    });
+    let args = body.args.iter()
+        .map(|a| match a {
+            serde_json::Value::String(s) => format!("\"{}\"", s),
+            other => other.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let entry = LogEntry {
+        id: uuid::Uuid::new_v4().to_string(),
+        timestamp: body.timestamp.unwrap_or_else(Local::now),
+        level: "info".to_string(),
+        message: format!("[{}] [{}] {}({})", direction, method, path, args),
+        source: Some("remote_spy".to_string()),
+        pid: body.pid,
+        username: body.username.clone(),
+        tags: vec!["spy".to_string(), direction.to_lowercase()],
+        repeat_count: 1,
+        fields: HashMap::new(),
+        data: Some(serde_json::json!({ "args": body.args })),
+    };
+    if let Some(client) = body.pid.map(|p| p.to_string()).or(body.username) {
+        state.spy_clients.write().insert(client);
+    }
+    store_spy_event(&state, &entry);
+    let id = entry.id.clone();
+    store_entry(&state, &entry);
+
+    HttpResponse::Ok().json(serde_json::json!({ "ok": true, "id": id }))
+}
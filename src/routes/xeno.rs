@@ -1,23 +1,50 @@
 use actix_web::{web, HttpRequest, HttpResponse};
+use base64::prelude::*;
+use futures_util::StreamExt;
+use regex::Regex;
 use chrono::Local;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::Ordering;
+use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
 use uuid::Uuid;
 
 use crate::loader::build_loader_lua;
 use crate::logger::build_logger_lua;
-use crate::models::{AppState, AttachLoggerRequest, ExecuteRequest, LogEntry, ServerMode};
-use crate::routes::logs::{check_secret, store_entry};
+use crate::models::{AppState, Args, AttachLoggerRequest, ClientLogsQuery, ClientsQuery, ExecuteRequest, ExecuteResult, GenericClient, LogEntry, ServerMode, XenoClient};
+use crate::routes::logs::{check_read_secret, check_secret, secure_eq, store_entry};
+use crate::routes::scripts::load_script;
 use crate::xeno::{xeno_execute, xeno_fetch_clients};
 
-pub async fn get_clients(state: web::Data<Arc<AppState>>) -> HttpResponse {
+/// Case-insensitive substring match for the `username` filter on GET /clients.
+fn username_matches(username: &str, filter: &Option<String>) -> bool {
+    filter.as_ref().is_none_or(|f| username.to_lowercase().contains(&f.to_lowercase()))
+}
+
+pub async fn get_clients(
+    req: HttpRequest,
+    query: web::Query<ClientsQuery>,
+    state: web::Data<Arc<AppState>>,
+) -> HttpResponse {
+    if let Err(resp) = check_read_secret(&req, &state) {
+        return resp;
+    }
     match state.args.mode {
         ServerMode::Xeno => {
-            match xeno_fetch_clients(&state).await {
-                Ok(clients) => HttpResponse::Ok().json(serde_json::json!({
-                    "ok": true,
-                    "clients": clients
-                })),
+            match xeno_fetch_clients(&state, query.fresh).await {
+                Ok(clients) => {
+                    let clients: Vec<XenoClient> = clients.into_iter()
+                        .filter(|c| !query.attached_only || c.status == 3)
+                        .filter(|c| query.with_logger.is_none_or(|w| c.logger_attached == w))
+                        .filter(|c| username_matches(&c.username, &query.username))
+                        .collect();
+                    HttpResponse::Ok().json(serde_json::json!({
+                        "ok": true,
+                        "clients": clients
+                    }))
+                }
                 Err(err) => HttpResponse::ServiceUnavailable().json(serde_json::json!({
                     "ok": false,
                     "error": err,
@@ -26,25 +53,310 @@ pub async fn get_clients(state: web::Data<Arc<AppState>>) -> HttpResponse {
             }
         }
         ServerMode::Generic => {
-            let clients = state.generic_clients.read();
-            let connected: Vec<_> = clients.values()
+            let clients: Vec<GenericClient> = state.generic_clients.read()
+                .values()
                 .filter(|c| c.connected)
-                .map(|c| serde_json::json!({
-                    "username": c.username,
-                    "connected": c.connected,
-                    "connected_at": c.connected_at.to_rfc3339(),
-                    "last_heartbeat": c.last_heartbeat.to_rfc3339(),
-                }))
+                .filter(|c| username_matches(&c.username, &query.username))
+                .cloned()
                 .collect();
             HttpResponse::Ok().json(serde_json::json!({
                 "ok": true,
                 "mode": "generic",
-                "clients": connected,
+                "clients": generic_clients_json(&state, &clients),
             }))
         }
     }
 }
 
+/// A single client: by PID in Xeno mode, by username in generic mode.
+pub async fn get_client(
+    req: HttpRequest,
+    path: web::Path<String>,
+    state: web::Data<Arc<AppState>>,
+) -> HttpResponse {
+    if let Err(resp) = check_read_secret(&req, &state) {
+        return resp;
+    }
+    let id = path.into_inner();
+    match state.args.mode {
+        ServerMode::Xeno => {
+            let clients = match xeno_fetch_clients(&state, false).await {
+                Ok(c) => c,
+                Err(err) => {
+                    return HttpResponse::ServiceUnavailable().json(serde_json::json!({
+                        "ok": false,
+                        "error": err,
+                        "status": 503
+                    }));
+                }
+            };
+            match clients.into_iter().find(|c| c.pid.to_string() == id) {
+                Some(client) => HttpResponse::Ok().json(serde_json::json!({
+                    "ok": true,
+                    "client": client,
+                })),
+                None => HttpResponse::NotFound().json(serde_json::json!({
+                    "ok": false,
+                    "error": format!("No Xeno client with PID {}", id),
+                    "status": 404
+                })),
+            }
+        }
+        ServerMode::Generic => {
+            let client = state.generic_clients.read().get(&id).cloned();
+            match client {
+                Some(client) => HttpResponse::Ok().json(serde_json::json!({
+                    "ok": true,
+                    "mode": "generic",
+                    "client": generic_clients_json(&state, &[client]).remove(0),
+                })),
+                None => HttpResponse::NotFound().json(serde_json::json!({
+                    "ok": false,
+                    "error": format!("No generic client with username '{}'", id),
+                    "status": 404
+                })),
+            }
+        }
+    }
+}
+
+/// Current clients keyed for diffing: PID in Xeno mode, username in generic mode.
+async fn client_rows(state: &AppState) -> Result<Vec<(String, serde_json::Value)>, String> {
+    match state.args.mode {
+        ServerMode::Xeno => Ok(xeno_fetch_clients(state, false).await?
+            .into_iter()
+            .map(|c| (c.pid.to_string(), serde_json::json!(c)))
+            .collect()),
+        ServerMode::Generic => {
+            let clients: Vec<GenericClient> = state.generic_clients.read()
+                .values()
+                .filter(|c| c.connected)
+                .cloned()
+                .collect();
+            let rows = generic_clients_json(state, &clients);
+            Ok(clients.into_iter().map(|c| c.username).zip(rows).collect())
+        }
+    }
+}
+
+/// `{"type":"diff", added, removed, changed}` between two client sets, or None
+/// when nothing changed. Heartbeat and last-log timestamps move constantly, so
+/// they don't count as a change on their own.
+fn clients_diff(
+    prev: &HashMap<String, serde_json::Value>,
+    current: &HashMap<String, serde_json::Value>,
+) -> Option<serde_json::Value> {
+    let stable = |v: &serde_json::Value| {
+        let mut v = v.clone();
+        if let Some(obj) = v.as_object_mut() {
+            obj.remove("last_heartbeat");
+            obj.remove("last_log_at");
+        }
+        v
+    };
+    let added: Vec<&serde_json::Value> = current.iter()
+        .filter(|(k, _)| !prev.contains_key(*k))
+        .map(|(_, v)| v)
+        .collect();
+    let removed: Vec<&String> = prev.keys().filter(|k| !current.contains_key(*k)).collect();
+    let changed: Vec<&serde_json::Value> = current.iter()
+        .filter(|(k, v)| prev.get(*k).is_some_and(|old| stable(old) != stable(v)))
+        .map(|(_, v)| v)
+        .collect();
+    if added.is_empty() && removed.is_empty() && changed.is_empty() {
+        return None;
+    }
+    Some(serde_json::json!({
+        "type": "diff",
+        "added": added,
+        "removed": removed,
+        "changed": changed,
+    }))
+}
+
+/// Re-check the client list every --clients-poll-ms (or right away after an
+/// attach/detach event) and broadcast diffs to GET /clients/ws subscribers.
+/// Nothing is fetched while nobody is subscribed.
+pub async fn watch_clients(state: Arc<AppState>) {
+    let period = Duration::from_millis(state.args.clients_poll_ms.max(100));
+    let mut last: Option<HashMap<String, serde_json::Value>> = None;
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(period) => {}
+            _ = state.clients_changed.notified() => {}
+        }
+        if state.clients_tx.receiver_count() == 0 {
+            last = None;
+            continue;
+        }
+        let Ok(rows) = client_rows(&state).await else {
+            continue;
+        };
+        let current: HashMap<String, serde_json::Value> = rows.into_iter().collect();
+        if let Some(diff) = last.as_ref().and_then(|prev| clients_diff(prev, &current)) {
+            let _ = state.clients_tx.send(diff.to_string());
+        }
+        last = Some(current);
+    }
+}
+
+/// WebSocket feed of the client list: a `snapshot` on connect, then a `diff`
+/// message whenever clients appear, disappear or change state.
+pub async fn clients_ws(
+    req: HttpRequest,
+    body: web::Payload,
+    state: web::Data<Arc<AppState>>,
+) -> Result<HttpResponse, actix_web::Error> {
+    if let Err(resp) = check_read_secret(&req, &state) {
+        return Ok(resp);
+    }
+    let (response, mut session, mut messages) = actix_ws::handle(&req, body)?;
+
+    let mut rx = state.clients_tx.subscribe();
+    state.clients_changed.notify_one();
+    let snapshot = match client_rows(&state).await {
+        Ok(rows) => serde_json::json!({
+            "type": "snapshot",
+            "clients": rows.into_iter().map(|(_, v)| v).collect::<Vec<_>>(),
+        }),
+        Err(err) => serde_json::json!({ "type": "error", "error": err }),
+    };
+
+    actix_web::rt::spawn(async move {
+        if session.text(snapshot.to_string()).await.is_err() {
+            return;
+        }
+        loop {
+            tokio::select! {
+                update = rx.recv() => match update {
+                    Ok(text) => {
+                        if session.text(text).await.is_err() {
+                            return;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                msg = messages.next() => match msg {
+                    Some(Ok(actix_ws::Message::Ping(bytes))) => {
+                        if session.pong(&bytes).await.is_err() {
+                            return;
+                        }
+                    }
+                    Some(Ok(actix_ws::Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
+        let _ = session.close(None).await;
+    });
+    Ok(response)
+}
+
+/// JSON rows for generic clients, with `last_log_at` taken from the newest
+/// stored log for each username (found in one backwards pass).
+fn generic_clients_json(state: &AppState, clients: &[GenericClient]) -> Vec<serde_json::Value> {
+    let mut last_log_at: HashMap<&str, String> = HashMap::new();
+    for e in state.logs.read().iter().rev() {
+        if last_log_at.len() == clients.len() {
+            break;
+        }
+        if let Some(name) = e.username.as_deref() {
+            if let Some(c) = clients.iter().find(|c| c.username == name) {
+                last_log_at.entry(c.username.as_str()).or_insert_with(|| e.timestamp.to_rfc3339());
+            }
+        }
+    }
+
+    // The loader embeds log forwarding, so generic clients are always logger-attached
+    clients.iter()
+        .map(|c| serde_json::json!({
+            "username": c.username,
+            "connected": c.connected,
+            "connected_at": c.connected_at.to_rfc3339(),
+            "last_heartbeat": c.last_heartbeat.to_rfc3339(),
+            "logger_attached": true,
+            "last_log_at": last_log_at.get(c.username.as_str()),
+        }))
+        .collect()
+}
+
+/// Most recent log entries for a single client, newest first. In generic mode
+/// the path segment is the client's username since generic clients have no PID.
+pub async fn get_client_logs(
+    req: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<ClientLogsQuery>,
+    state: web::Data<Arc<AppState>>,
+) -> HttpResponse {
+    if let Err(resp) = check_read_secret(&req, &state) {
+        return resp;
+    }
+    let id = path.into_inner();
+    let limit = query.limit.unwrap_or(100).min(1000);
+
+    let pid_filter = match state.args.mode {
+        ServerMode::Xeno => {
+            let pid = match id.parse::<u64>() {
+                Ok(p) => p,
+                Err(_) => {
+                    return HttpResponse::BadRequest().json(serde_json::json!({
+                        "ok": false,
+                        "error": format!("'{}' is not a valid PID", id),
+                        "status": 400
+                    }));
+                }
+            };
+            match xeno_fetch_clients(&state, false).await {
+                Ok(clients) if clients.iter().any(|c| c.pid == pid) => {}
+                Ok(_) => {
+                    return HttpResponse::NotFound().json(serde_json::json!({
+                        "ok": false,
+                        "error": format!("PID {} was not found in Xeno", pid),
+                        "status": 404
+                    }));
+                }
+                Err(err) => {
+                    return HttpResponse::ServiceUnavailable().json(serde_json::json!({
+                        "ok": false,
+                        "error": err,
+                        "status": 503
+                    }));
+                }
+            }
+            Some(pid)
+        }
+        ServerMode::Generic => {
+            if !state.generic_clients.read().contains_key(&id) {
+                return HttpResponse::NotFound().json(serde_json::json!({
+                    "ok": false,
+                    "error": format!("Client '{}' is not known", id),
+                    "status": 404
+                }));
+            }
+            None
+        }
+    };
+
+    let logs = state.logs.read();
+    let mut entries: Vec<&LogEntry> = logs
+        .iter()
+        .filter(|e| match pid_filter {
+            Some(pid) => e.pid == Some(pid),
+            None => e.username.as_deref() == Some(id.as_str()),
+        })
+        .collect();
+    entries.sort_by_key(|e| std::cmp::Reverse(e.timestamp));
+    entries.truncate(limit);
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "ok": true,
+        "client": id,
+        "count": entries.len(),
+        "logs": entries,
+    }))
+}
+
 pub async fn post_execute(
     req: HttpRequest,
     body: web::Json<ExecuteRequest>,
@@ -56,56 +368,119 @@ pub async fn post_execute(
 
     let req_body = body.into_inner();
 
-    if req_body.script.trim().is_empty() {
+    // `script_b64` carries payloads that don't survive a JSON string (NULs, non-UTF-8);
+    // `script_name` runs a script saved via POST /scripts
+    let script: Vec<u8> = if !req_body.script.is_empty() {
+        req_body.script.clone().into_bytes()
+    } else if let Some(b64) = req_body.script_b64.as_deref() {
+        match BASE64_STANDARD.decode(b64.trim()) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "ok": false,
+                    "error": format!("script_b64 is not valid base64: {}", err),
+                    "status": 400
+                }));
+            }
+        }
+    } else if let Some(name) = req_body.script_name.as_deref() {
+        match load_script(&state, name) {
+            Ok(bytes) => bytes,
+            Err(resp) => return resp,
+        }
+    } else {
+        Vec::new()
+    };
+
+    if script.iter().all(u8::is_ascii_whitespace) {
         return HttpResponse::BadRequest().json(serde_json::json!({
             "ok": false,
-            "error": "script must not be empty",
+            "error": "script (or script_b64 / script_name) must not be empty",
+            "status": 400
+        }));
+    }
+
+    let denied = denied_identifiers(&state.args.execute_denylist, &String::from_utf8_lossy(&script));
+    if !denied.is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "ok": false,
+            "error": format!("Script uses denied identifiers: {}", denied.join(", ")),
+            "denied": denied,
             "status": 400
         }));
     }
 
     match state.args.mode {
-        ServerMode::Generic => post_execute_generic(&req_body, &state),
-        ServerMode::Xeno => post_execute_xeno(req_body, &state).await,
+        ServerMode::Generic => post_execute_generic(&req_body, &script, &state).await,
+        ServerMode::Xeno => post_execute_xeno(req_body, &script, &state).await,
     }
 }
 
-fn post_execute_generic(
+async fn post_execute_generic(
     req_body: &ExecuteRequest,
+    script: &[u8],
     state: &web::Data<Arc<AppState>>,
 ) -> HttpResponse {
     let file_id = Uuid::new_v4().to_string();
     let file_path = format!("{}/pending/{}.lua", state.args.exchange_dir, file_id);
 
+    if req_body.dry_run {
+        return HttpResponse::Ok().json(serde_json::json!({
+            "ok": true,
+            "mode": "generic",
+            "dry_run": true,
+            "file_id": file_id,
+            "file_path": file_path,
+            "message": "Dry run: script was not written to the exchange directory.",
+        }));
+    }
+
+    if let Err(resp) = check_rate_limit(state, &["*".to_string()]) {
+        return resp;
+    }
+
     // Sign the script if a secret is configured
     let file_content = if let Some(ref secret) = state.args.secret {
-        let sig = hex::encode(hmac_sha256::HMAC::mac(req_body.script.as_bytes(), secret.as_bytes()));
-        format!("-- SIG:{}\n{}", sig, req_body.script)
+        let sig = hex::encode(hmac_sha256::HMAC::mac(script, secret.as_bytes()));
+        [format!("-- SIG:{}\n", sig).as_bytes(), script].concat()
     } else {
-        req_body.script.clone()
+        script.to_vec()
     };
 
+    let output_rx = req_body.wait_ms.map(|_| state.log_tx.subscribe());
     match std::fs::write(&file_path, &file_content) {
         Ok(()) => {
+            state.metrics.executes_generic.fetch_add(1, Ordering::Relaxed);
+            state.pending_results.write().insert(file_id.clone(), Local::now());
             // Log the script execution
             let entry = LogEntry {
                 id: Uuid::new_v4().to_string(),
                 timestamp: Local::now(),
                 level: "script".to_string(),
-                message: req_body.script.clone(),
+                message: String::from_utf8_lossy(script).into_owned(),
                 source: Some("execute_lua".to_string()),
                 pid: None,
                 username: None,
                 tags: vec!["script".to_string(), "executed".to_string(), "generic".to_string()],
+                repeat_count: 1,
+                fields: HashMap::new(),
+                data: None,
             };
             store_entry(state, &entry);
 
-            HttpResponse::Ok().json(serde_json::json!({
+            let mut result = serde_json::json!({
                 "ok": true,
                 "mode": "generic",
                 "file_id": file_id,
                 "message": "Script written to exchange directory. Loader will pick it up.",
-            }))
+            });
+            if let (Some(rx), Some(wait_ms)) = (output_rx, req_body.wait_ms) {
+                let result_path = format!("{}/results/{}.json", state.args.exchange_dir, file_id);
+                let (output, timed_out) = collect_output(rx, wait_ms, &[], Some(&result_path)).await;
+                result["output"] = serde_json::json!(output);
+                result["output_timed_out"] = serde_json::json!(timed_out);
+            }
+            HttpResponse::Ok().json(result)
         }
         Err(err) => HttpResponse::InternalServerError().json(serde_json::json!({
             "ok": false,
@@ -115,8 +490,232 @@ fn post_execute_generic(
     }
 }
 
+/// Entries of --execute-denylist that appear in `script` as whole words. This is
+/// a coarse textual check: comments and strings count, and obfuscated code
+/// (`getfenv()["load".."string"]`) slips through.
+fn denied_identifiers(denylist: &[String], script: &str) -> Vec<String> {
+    denylist.iter()
+        .map(|t| t.trim())
+        .filter(|t| !t.is_empty())
+        .filter(|t| {
+            Regex::new(&format!(r"\b{}\b", regex::escape(t)))
+                .is_ok_and(|re| re.is_match(script))
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+/// 429 with a `retry_after` hint when any target has used up its --execute-rate-per-min budget.
+fn execute_rate_text(args: &Args) -> String {
+    match (args.execute_rate, args.execute_rate_per_min) {
+        (Some(per_sec), _) => format!("{}/s", per_sec),
+        (None, Some(per_min)) => format!("{}/min", per_min),
+        (None, None) => "unlimited".to_string(),
+    }
+}
+
+fn check_rate_limit(state: &AppState, keys: &[String]) -> Result<(), HttpResponse> {
+    state.execute_limiter.try_acquire(keys).map_err(|wait| {
+        let retry_after = wait.ceil() as u64;
+        HttpResponse::TooManyRequests()
+            .insert_header(("Retry-After", retry_after.to_string()))
+            .json(serde_json::json!({
+                "ok": false,
+                "error": format!("Execute rate limit reached ({}). Retry in {}s", execute_rate_text(&state.args), retry_after),
+                "retry_after": retry_after,
+                "status": 429
+            }))
+    })
+}
+
+/// Upper bound for `wait_ms` so a single request can't hold a worker indefinitely
+const MAX_OUTPUT_WAIT_MS: u64 = 30_000;
+
+/// Gather client output broadcast after a dispatch for up to `wait_ms`. Entries
+/// must come from one of `pids` (any client when empty); the server's own
+/// entries and the `script` echo are skipped. When `result_path` is given
+/// (generic mode) collection ends early once the loader writes that file.
+/// Returns the entries and whether the wait ran out.
+async fn collect_output(
+    mut rx: broadcast::Receiver<LogEntry>,
+    wait_ms: u64,
+    pids: &[String],
+    result_path: Option<&str>,
+) -> (Vec<LogEntry>, bool) {
+    let is_output = |e: &LogEntry| {
+        e.level != "script"
+            && e.source.as_deref() != Some("xeno-mcp")
+            && (pids.is_empty() || e.pid.is_some_and(|p| pids.contains(&p.to_string())))
+    };
+    let deadline = tokio::time::Instant::now() + Duration::from_millis(wait_ms.min(MAX_OUTPUT_WAIT_MS));
+    let mut poll = tokio::time::interval(Duration::from_millis(100));
+    let mut output = Vec::new();
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep_until(deadline) => return (output, true),
+            _ = poll.tick(), if result_path.is_some() => {
+                if result_path.is_some_and(|p| Path::new(p).exists()) {
+                    while let Ok(e) = rx.try_recv() {
+                        if is_output(&e) {
+                            output.push(e);
+                        }
+                    }
+                    return (output, false);
+                }
+            }
+            msg = rx.recv() => match msg {
+                Ok(e) => {
+                    if is_output(&e) {
+                        output.push(e);
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return (output, true),
+            },
+        }
+    }
+}
+
+/// Success payload for a Xeno execute: targets plus per-PID logger state.
+fn execute_report(state: &AppState, targets: &[String]) -> serde_json::Value {
+    let logger_pids = state.logger_pids.read();
+    let mut logger_status: Vec<serde_json::Value> = Vec::new();
+    for pid in targets {
+        logger_status.push(serde_json::json!({
+            "pid": pid,
+            "logger_attached": logger_pids.contains(pid),
+        }));
+    }
+    let pids_without_logger: Vec<&String> = targets.iter()
+        .filter(|p| !logger_pids.contains(*p))
+        .collect();
+
+    let mut result = serde_json::json!({
+        "ok": true,
+        "executed_on": targets,
+        "logger_status": logger_status,
+    });
+    if !pids_without_logger.is_empty() {
+        result["warning"] = serde_json::json!(
+            format!("Logger is not attached on PIDs: {}. Script output will not be captured. Use POST /attach-logger first.",
+                pids_without_logger.iter().map(|p| p.as_str()).collect::<Vec<_>>().join(", "))
+        );
+    }
+    result
+}
+
+/// Result of a generic-mode execution, read from the file the loader writes
+/// into `{exchange_dir}/results/`. Reports "pending" until it appears, and 404
+/// once --result-timeout-secs has passed without one.
+pub async fn get_execute_result(
+    req: HttpRequest,
+    path: web::Path<String>,
+    state: web::Data<Arc<AppState>>,
+) -> HttpResponse {
+    if let Err(resp) = check_read_secret(&req, &state) {
+        return resp;
+    }
+    if matches!(state.args.mode, ServerMode::Xeno) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "ok": false,
+            "error": "Execution results are only available in generic mode",
+            "status": 400
+        }));
+    }
+
+    let file_id = path.into_inner();
+    if Uuid::parse_str(&file_id).is_err() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "ok": false,
+            "error": format!("'{}' is not a valid file_id", file_id),
+            "status": 400
+        }));
+    }
+
+    let result_path = format!("{}/results/{}.json", state.args.exchange_dir, file_id);
+    if let Ok(raw) = std::fs::read_to_string(&result_path) {
+        return match serde_json::from_str::<ExecuteResult>(&raw) {
+            Ok(result) => {
+                state.pending_results.write().remove(&file_id);
+                HttpResponse::Ok().json(serde_json::json!({ "ok": true, "result": result }))
+            }
+            Err(err) => HttpResponse::InternalServerError().json(serde_json::json!({
+                "ok": false,
+                "error": format!("Malformed result file for {}: {}", file_id, err),
+                "status": 500
+            })),
+        };
+    }
+
+    let dispatched_at = state.pending_results.read().get(&file_id).copied();
+    match dispatched_at {
+        Some(at) if (Local::now() - at).num_seconds() < state.args.result_timeout_secs as i64 => {
+            HttpResponse::Ok().json(serde_json::json!({
+                "ok": true,
+                "result": ExecuteResult {
+                    file_id,
+                    status: "pending".to_string(),
+                    output: Vec::new(),
+                    returns: Vec::new(),
+                    error: None,
+                    finished_at: None,
+                },
+            }))
+        }
+        _ => {
+            state.pending_results.write().remove(&file_id);
+            HttpResponse::NotFound().json(serde_json::json!({
+                "ok": false,
+                "error": format!("No result for {} (unknown id, or the loader did not report back within {}s)", file_id, state.args.result_timeout_secs),
+                "status": 404
+            }))
+        }
+    }
+}
+
+/// Forget generic executes dispatched more than --result-timeout-secs ago and
+/// delete result files the loader wrote longer ago than that, polled or not.
+/// Run from the generic-mode reaper task.
+pub fn sweep_execute_results(state: &AppState) {
+    let timeout_secs = state.args.result_timeout_secs as i64;
+    let now = Local::now();
+    let expired: Vec<String> = {
+        let mut pending = state.pending_results.write();
+        let expired = pending.iter()
+            .filter(|(_, at)| (now - **at).num_seconds() >= timeout_secs)
+            .map(|(id, _)| id.clone())
+            .collect::<Vec<_>>();
+        for id in &expired {
+            pending.remove(id);
+        }
+        expired
+    };
+    let results_dir = Path::new(&state.args.exchange_dir).join("results");
+    for id in &expired {
+        let _ = std::fs::remove_file(results_dir.join(format!("{}.json", id)));
+    }
+
+    let Ok(dir) = std::fs::read_dir(&results_dir) else { return };
+    let max_age = Duration::from_secs(state.args.result_timeout_secs);
+    for entry in dir.flatten() {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let stale = entry.metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.elapsed().ok())
+            .is_some_and(|age| age >= max_age);
+        if stale {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
 async fn post_execute_xeno(
     req_body: ExecuteRequest,
+    script: &[u8],
     state: &web::Data<Arc<AppState>>,
 ) -> HttpResponse {
     if req_body.pids.is_empty() {
@@ -127,7 +726,7 @@ async fn post_execute_xeno(
         }));
     }
 
-    let clients = match xeno_fetch_clients(state).await {
+    let clients = match xeno_fetch_clients(state, false).await {
         Ok(c) => c,
         Err(err) => {
             return HttpResponse::ServiceUnavailable().json(serde_json::json!({
@@ -138,11 +737,30 @@ async fn post_execute_xeno(
         }
     };
 
+    // `["*"]` targets every client currently in 'Attached' state
+    let requested: Vec<String> = if req_body.pids.len() == 1 && req_body.pids[0] == "*" {
+        let attached: Vec<String> = clients.iter()
+            .filter(|c| c.status == 3)
+            .map(|c| c.pid.to_string())
+            .collect();
+        if attached.is_empty() {
+            return HttpResponse::Conflict().json(serde_json::json!({
+                "ok": false,
+                "error": "No clients are in 'Attached' state. Inject Xeno into a Roblox client first, or check GET /clients",
+                "status": 409
+            }));
+        }
+        attached
+    } else {
+        req_body.pids.clone()
+    };
+
     let known_pids: HashSet<String> = clients.iter().map(|c| c.pid.to_string()).collect();
     let mut not_found = Vec::new();
     let mut not_attached = Vec::new();
+    let mut targets = Vec::new();
 
-    for pid in &req_body.pids {
+    for pid in &requested {
         if !known_pids.contains(pid) {
             not_found.push(pid.clone());
         } else if let Some(client) = clients.iter().find(|c| c.pid.to_string() == *pid) {
@@ -151,19 +769,30 @@ async fn post_execute_xeno(
                     "pid": pid,
                     "status": client.status_text,
                 }));
+            } else {
+                targets.push(pid.clone());
             }
         }
     }
 
-    if !not_found.is_empty() {
+    if req_body.allow_partial {
+        if targets.is_empty() {
+            return HttpResponse::Conflict().json(serde_json::json!({
+                "ok": false,
+                "error": "None of the requested PIDs are known and in 'Attached' state",
+                "not_found": not_found,
+                "not_attached": not_attached,
+                "status": 409
+            }));
+        }
+    } else if !not_found.is_empty() {
         return HttpResponse::NotFound().json(serde_json::json!({
             "ok": false,
             "error": "Some PIDs were not found in Xeno",
             "not_found": not_found,
             "status": 404
         }));
-    }
-    if !not_attached.is_empty() {
+    } else if !not_attached.is_empty() {
         return HttpResponse::Conflict().json(serde_json::json!({
             "ok": false,
             "error": "Some PIDs are not in 'Attached' state",
@@ -172,9 +801,25 @@ async fn post_execute_xeno(
         }));
     }
 
-    match xeno_execute(state, &req_body.script, &req_body.pids).await {
-        Ok(()) => {
-            let target_names: Vec<String> = req_body.pids.iter().map(|pid| {
+    if req_body.dry_run {
+        let mut result = execute_report(state, &targets);
+        result["dry_run"] = serde_json::json!(true);
+        if req_body.allow_partial {
+            result["not_found"] = serde_json::json!(not_found);
+            result["not_attached"] = serde_json::json!(not_attached);
+        }
+        return HttpResponse::Ok().json(result);
+    }
+
+    if let Err(resp) = check_rate_limit(state, &targets) {
+        return resp;
+    }
+
+    let output_rx = req_body.wait_ms.map(|_| state.log_tx.subscribe());
+    match xeno_execute(state, script, &targets).await {
+        Ok(outcome) => {
+            state.metrics.executes_xeno.fetch_add(1, Ordering::Relaxed);
+            let target_names: Vec<String> = targets.iter().map(|pid| {
                 clients.iter()
                     .find(|c| c.pid.to_string() == *pid)
                     .map(|c| format!("{}({})", c.username, c.pid))
@@ -184,42 +829,35 @@ async fn post_execute_xeno(
                 id: Uuid::new_v4().to_string(),
                 timestamp: Local::now(),
                 level: "script".to_string(),
-                message: req_body.script.clone(),
+                message: String::from_utf8_lossy(script).into_owned(),
                 source: Some("execute_lua".to_string()),
-                pid: if req_body.pids.len() == 1 { req_body.pids[0].parse::<u64>().ok() } else { None },
-                username: if req_body.pids.len() == 1 {
-                    clients.iter().find(|c| c.pid.to_string() == req_body.pids[0]).map(|c| c.username.clone())
+                pid: if targets.len() == 1 { targets[0].parse::<u64>().ok() } else { None },
+                username: if targets.len() == 1 {
+                    clients.iter().find(|c| c.pid.to_string() == targets[0]).map(|c| c.username.clone())
                 } else { None },
                 tags: {
                     let mut t = vec!["script".to_string(), "executed".to_string()];
                     for name in &target_names { t.push(name.clone()); }
                     t
                 },
+                repeat_count: 1,
+                fields: HashMap::new(),
+                data: None,
             };
             store_entry(state, &entry);
 
-            let logger_pids = state.logger_pids.read();
-            let mut logger_status: Vec<serde_json::Value> = Vec::new();
-            for pid in &req_body.pids {
-                logger_status.push(serde_json::json!({
-                    "pid": pid,
-                    "logger_attached": logger_pids.contains(pid),
-                }));
+            let mut result = execute_report(state, &targets);
+            // Xeno may answer with a job id or per-client status; anything non-JSON becomes {}
+            result["xeno_response"] = serde_json::from_str(&outcome.body).unwrap_or_else(|_| serde_json::json!({}));
+            result["xeno"] = serde_json::json!(outcome);
+            if req_body.allow_partial {
+                result["not_found"] = serde_json::json!(not_found);
+                result["not_attached"] = serde_json::json!(not_attached);
             }
-            let pids_without_logger: Vec<&String> = req_body.pids.iter()
-                .filter(|p| !logger_pids.contains(*p))
-                .collect();
-
-            let mut result = serde_json::json!({
-                "ok": true,
-                "executed_on": req_body.pids,
-                "logger_status": logger_status,
-            });
-            if !pids_without_logger.is_empty() {
-                result["warning"] = serde_json::json!(
-                    format!("Logger is not attached on PIDs: {}. Script output will not be captured. Use POST /attach-logger first.",
-                        pids_without_logger.iter().map(|p| p.as_str()).collect::<Vec<_>>().join(", "))
-                );
+            if let (Some(rx), Some(wait_ms)) = (output_rx, req_body.wait_ms) {
+                let (output, timed_out) = collect_output(rx, wait_ms, &targets, None).await;
+                result["output"] = serde_json::json!(output);
+                result["output_timed_out"] = serde_json::json!(timed_out);
             }
             HttpResponse::Ok().json(result)
         }
@@ -231,6 +869,44 @@ async fn post_execute_xeno(
     }
 }
 
+/// --auto-reattach: re-send the logger to Attached clients that have none,
+/// except those detached on purpose via POST /detach-logger. Returns the PIDs
+/// the logger was sent to.
+pub async fn reattach_missing_loggers(state: &AppState, clients: &[XenoClient]) -> Result<Vec<String>, String> {
+    let missing: Vec<String> = {
+        let active: HashSet<String> = clients.iter().map(|c| c.pid.to_string()).collect();
+        // Same lock order as post_detach_logger: logger_pids, then logger_detached
+        let logger_pids = state.logger_pids.read();
+        let mut detached = state.logger_detached.write();
+        detached.retain(|pid| active.contains(pid));
+        clients.iter()
+            .filter(|c| c.status == 3)
+            .map(|c| c.pid.to_string())
+            .filter(|pid| !logger_pids.contains(pid) && !detached.contains(pid))
+            .collect()
+    };
+    if missing.is_empty() {
+        return Ok(missing);
+    }
+    let lua = build_logger_lua(state.args.port, &state.args.secret);
+    xeno_execute(state, &lua, &missing).await?;
+    *state.last_reattach.lock() = Some((Local::now(), missing.clone()));
+    Ok(missing)
+}
+
+/// PIDs an attach/detach request is aimed at: every Attached client when
+/// `all` is set, otherwise the explicit `pids` list.
+fn logger_targets(req: &AttachLoggerRequest, clients: &[XenoClient]) -> Vec<String> {
+    if req.all {
+        clients.iter()
+            .filter(|c| c.status == 3)
+            .map(|c| c.pid.to_string())
+            .collect()
+    } else {
+        req.pids.clone()
+    }
+}
+
 pub async fn post_attach_logger(
     req: HttpRequest,
     body: web::Json<AttachLoggerRequest>,
@@ -253,15 +929,15 @@ pub async fn post_attach_logger(
 
     let req_body = body.into_inner();
 
-    if req_body.pids.is_empty() {
+    if req_body.pids.is_empty() && !req_body.all {
         return HttpResponse::BadRequest().json(serde_json::json!({
             "ok": false,
-            "error": "pids array must not be empty",
+            "error": "pids array must not be empty unless all is true",
             "status": 400
         }));
     }
 
-    let clients = match xeno_fetch_clients(&state).await {
+    let clients = match xeno_fetch_clients(&state, false).await {
         Ok(c) => c,
         Err(err) => {
             return HttpResponse::ServiceUnavailable().json(serde_json::json!({
@@ -272,6 +948,7 @@ pub async fn post_attach_logger(
         }
     };
 
+    let targets = logger_targets(&req_body, &clients);
     let known_pids: HashSet<String> = clients.iter().map(|c| c.pid.to_string()).collect();
     let mut not_found = Vec::new();
     let mut not_attached = Vec::new();
@@ -280,7 +957,7 @@ pub async fn post_attach_logger(
 
     {
         let logger_pids = state.logger_pids.read();
-        for pid in &req_body.pids {
+        for pid in &targets {
             if !known_pids.contains(pid) {
                 not_found.push(pid.clone());
             } else if let Some(client) = clients.iter().find(|c| c.pid.to_string() == *pid) {
@@ -316,9 +993,15 @@ pub async fn post_attach_logger(
     }
 
     if to_attach.is_empty() {
+        let message = if targets.is_empty() {
+            "No clients are in 'Attached' state"
+        } else {
+            "Logger already attached on all requested PIDs"
+        };
         return HttpResponse::Ok().json(serde_json::json!({
             "ok": true,
-            "message": "Logger already attached on all requested PIDs",
+            "message": message,
+            "targets": targets,
             "already_attached": already_attached
         }));
     }
@@ -326,10 +1009,17 @@ pub async fn post_attach_logger(
     let lua = build_logger_lua(state.args.port, &state.args.secret);
 
     match xeno_execute(&state, &lua, &to_attach).await {
-        Ok(()) => {
+        Ok(_) => {
+            {
+                let mut detached = state.logger_detached.write();
+                for pid in &to_attach {
+                    detached.remove(pid);
+                }
+            }
             let mut result = serde_json::json!({
                 "ok": true,
                 "message": "Logger script sent. Awaiting client confirmation via /internal.",
+                "targets": targets,
                 "sent_to": to_attach
             });
             if !already_attached.is_empty() {
@@ -345,7 +1035,149 @@ pub async fn post_attach_logger(
     }
 }
 
-pub async fn get_loader_script(state: web::Data<Arc<AppState>>) -> HttpResponse {
+/// Disconnect the logger hooks on the given PIDs and stop tracking them as attached.
+pub async fn post_detach_logger(
+    req: HttpRequest,
+    body: web::Json<AttachLoggerRequest>,
+    state: web::Data<Arc<AppState>>,
+) -> HttpResponse {
+    if let Err(resp) = check_secret(&req, &state) {
+        return resp;
+    }
+
+    match state.args.mode {
+        ServerMode::Generic => {
+            return HttpResponse::Ok().json(serde_json::json!({
+                "ok": true,
+                "mode": "generic",
+                "message": "In generic mode, the logger is embedded in the loader script and cannot be detached separately.",
+            }));
+        }
+        ServerMode::Xeno => {}
+    }
+
+    let req_body = body.into_inner();
+
+    if req_body.pids.is_empty() && !req_body.all {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "ok": false,
+            "error": "pids array must not be empty unless all is true",
+            "status": 400
+        }));
+    }
+
+    let clients = match xeno_fetch_clients(&state, false).await {
+        Ok(c) => c,
+        Err(err) => {
+            return HttpResponse::ServiceUnavailable().json(serde_json::json!({
+                "ok": false,
+                "error": err,
+                "status": 503
+            }));
+        }
+    };
+
+    let mut not_found = Vec::new();
+    let mut client_not_attached = Vec::new();
+    let mut not_attached = Vec::new();
+    let mut to_detach = Vec::new();
+
+    let targets = logger_targets(&req_body, &clients);
+
+    {
+        let logger_pids = state.logger_pids.read();
+        for pid in &targets {
+            match clients.iter().find(|c| c.pid.to_string() == *pid) {
+                None => not_found.push(pid.clone()),
+                Some(client) if client.status != 3 => {
+                    client_not_attached.push(serde_json::json!({
+                        "pid": pid,
+                        "status": client.status_text,
+                    }));
+                }
+                Some(_) if logger_pids.contains(pid) => to_detach.push(pid.clone()),
+                Some(_) => not_attached.push(pid.clone()),
+            }
+        }
+    }
+
+    if !not_found.is_empty() {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "ok": false,
+            "error": "Some PIDs were not found in Xeno",
+            "not_found": not_found,
+            "status": 404
+        }));
+    }
+    if !client_not_attached.is_empty() {
+        return HttpResponse::Conflict().json(serde_json::json!({
+            "ok": false,
+            "error": "Some PIDs are not in 'Attached' state",
+            "not_attached": client_not_attached,
+            "status": 409
+        }));
+    }
+
+    if to_detach.is_empty() {
+        return HttpResponse::Ok().json(serde_json::json!({
+            "ok": true,
+            "message": "Logger is not attached on any requested PID",
+            "targets": targets,
+            "detached": [],
+            "not_attached": not_attached
+        }));
+    }
+
+    let detach_lua = r#"if getgenv().__XENO_MCP_LOGGER and type(getgenv().__XENO_MCP_LOGGER) == "table" then getgenv().__XENO_MCP_LOGGER.Disconnect() else getgenv().__XENO_MCP_LOGGER = nil end"#;
+
+    match xeno_execute(&state, detach_lua, &to_detach).await {
+        Ok(_) => {
+            {
+                let mut logger_pids = state.logger_pids.write();
+                let mut detached = state.logger_detached.write();
+                for pid in &to_detach {
+                    logger_pids.remove(pid);
+                    detached.insert(pid.clone());
+                }
+            }
+            state.clients_changed.notify_one();
+            HttpResponse::Ok().json(serde_json::json!({
+                "ok": true,
+                "message": "Logger detach script sent",
+                "targets": targets,
+                "detached": to_detach,
+                "not_attached": not_attached
+            }))
+        }
+        Err(err) => HttpResponse::BadGateway().json(serde_json::json!({
+            "ok": false,
+            "error": format!("Failed to execute detach script via Xeno: {}", err),
+            "status": 502
+        })),
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct LoaderScriptQuery {
+    pub secret: Option<String>,
+}
+
+/// The generic loader embeds the secret, so --gate-reads covers it too. `game:HttpGet`
+/// can't set headers, so the secret may also be passed as `?secret=`.
+pub async fn get_loader_script(
+    req: HttpRequest,
+    query: web::Query<LoaderScriptQuery>,
+    state: web::Data<Arc<AppState>>,
+) -> HttpResponse {
+    if let Err(resp) = check_read_secret(&req, &state) {
+        let query_ok = matches!(
+            (&query.secret, &state.args.secret),
+            (Some(given), Some(secret)) if secure_eq(given.as_bytes(), secret.as_bytes())
+        );
+        if !query_ok {
+            return resp;
+        }
+    }
     let lua = build_loader_lua(state.args.port, &state.args.secret, &state.args.exchange_dir, &state.args.executor_exchange_dir);
     HttpResponse::Ok()
         .content_type("text/plain; charset=utf-8")
@@ -371,7 +1203,7 @@ pub async fn post_verify_script(
     };
 
     let expected = hex::encode(hmac_sha256::HMAC::mac(body.script.as_bytes(), secret.as_bytes()));
-    let valid = body.signature == expected;
+    let valid = secure_eq(body.signature.as_bytes(), expected.as_bytes());
 
     HttpResponse::Ok().json(serde_json::json!({
         "ok": true,
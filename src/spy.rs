@@ -1,8 +1,24 @@
 const TEMPLATE: &str = include_str!("../lua/spy.lua.tpl");
+const XENO_TEMPLATE: &str = include_str!("../lua/spy_xeno.lua.tpl");
 
-pub fn build_spy_lua(server_port: u16, secret: &Option<String>) -> String {
+/// Levels of nested argument tables the spy expands when none is requested.
+pub const DEFAULT_MAX_ARG_DEPTH: u32 = 2;
+/// Upper bound accepted for `max_arg_depth`, to keep spy lines bounded.
+pub const MAX_ARG_DEPTH_LIMIT: u32 = 8;
+
+pub fn build_spy_lua(server_port: u16, secret: &Option<String>, max_arg_depth: u32) -> String {
+    render(TEMPLATE, server_port, secret, max_arg_depth)
+}
+
+/// Incoming-only spy for Xeno, which has no UNC hooks (--spy-allow-xeno).
+pub fn build_spy_xeno_lua(server_port: u16, secret: &Option<String>, max_arg_depth: u32) -> String {
+    render(XENO_TEMPLATE, server_port, secret, max_arg_depth)
+}
+
+fn render(template: &str, server_port: u16, secret: &Option<String>, max_arg_depth: u32) -> String {
     let secret_val = secret.as_deref().unwrap_or("");
-    TEMPLATE
+    template
         .replace("{{PORT}}", &server_port.to_string())
         .replace("{{SECRET}}", secret_val)
+        .replace("{{MAX_ARG_DEPTH}}", &max_arg_depth.to_string())
 }
@@ -1,4 +1,9 @@
-use crate::models::{AppState, XenoClient};
+use chrono::Local;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::models::{AppState, LogEntry, XenoClient, XenoExecuteOutcome};
+use crate::routes::logs::store_entry;
 
 pub fn status_text(code: u8) -> &'static str {
     match code {
@@ -10,75 +15,318 @@ pub fn status_text(code: u8) -> &'static str {
     }
 }
 
-pub async fn xeno_fetch_clients(state: &AppState) -> Result<Vec<XenoClient>, String> {
-    let url = format!("{}/o", state.args.xeno_url);
-    let resp = state
-        .http_client
-        .get(&url)
-        .send()
-        .await
-        .map_err(|e| format!("Cannot reach Xeno at {}: {}", url, e))?;
+/// Xeno client list, served from a short-lived cache (--clients-cache-ms) unless
+/// `force` is set. `logger_attached` always reflects the current logger PIDs.
+pub async fn xeno_fetch_clients(state: &AppState, force: bool) -> Result<Vec<XenoClient>, String> {
+    let ttl = Duration::from_millis(state.args.clients_cache_ms);
+    if !force && !ttl.is_zero() {
+        if let Some((fetched_at, ref clients)) = *state.client_cache.read() {
+            if fetched_at.elapsed() < ttl {
+                return Ok(with_logger_flags(state, clients.clone()));
+            }
+        }
+    }
+
+    let clients = fetch_clients_uncached(state).await?;
+    *state.client_cache.write() = Some((Instant::now(), clients.clone()));
+    Ok(with_logger_flags(state, clients))
+}
+
+fn with_logger_flags(state: &AppState, mut clients: Vec<XenoClient>) -> Vec<XenoClient> {
+    let logger_pids = state.logger_pids.read();
+    for c in clients.iter_mut() {
+        c.logger_attached = logger_pids.contains(&c.pid.to_string());
+    }
+    clients
+}
+
+fn request_error(state: &AppState, url: &str, err: reqwest::Error) -> String {
+    if err.is_timeout() {
+        format!("Xeno request timed out after {}ms ({})", state.args.xeno_timeout_ms, url)
+    } else {
+        format!("Cannot reach Xeno at {}: {}", url, err)
+    }
+}
+
+/// Send a Xeno request, retrying up to --xeno-retries times with exponential
+/// backoff (100ms, 200ms, ...) when the connection itself fails. Timeouts and
+/// HTTP error statuses are returned as-is: the request may already have run.
+async fn send_with_retry(
+    state: &AppState,
+    url: &str,
+    req: reqwest::RequestBuilder,
+) -> Result<reqwest::Response, reqwest::Error> {
+    send_with_backoff(req, state.args.xeno_retries, |attempt, delay, err| {
+        log_retry(state, url, attempt, &err.to_string(), delay)
+    })
+    .await
+}
+
+/// The retry loop behind `send_with_retry`; `on_retry` runs before each backoff sleep.
+async fn send_with_backoff(
+    req: reqwest::RequestBuilder,
+    retries: u32,
+    mut on_retry: impl FnMut(u32, Duration, &reqwest::Error),
+) -> Result<reqwest::Response, reqwest::Error> {
+    let mut delay = Duration::from_millis(100);
+    for attempt in 1..=retries {
+        let Some(req) = req.try_clone() else { break };
+        match req.send().await {
+            Err(e) if e.is_connect() => {
+                on_retry(attempt, delay, &e);
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+            result => return result,
+        }
+    }
+    req.send().await
+}
+
+/// GET the client list. Unlike `send_with_retry` this also retries 5xx
+/// answers, since listing clients is safe to repeat; 4xx is returned at once.
+/// Each retry is logged with the `retry` tag.
+async fn get_clients_with_retry(state: &AppState, url: &str) -> Result<reqwest::Response, String> {
+    let mut delay = Duration::from_millis(100);
+    let mut attempt = 0;
+    loop {
+        let (reason, err) = match state.http_client.get(url).send().await {
+            Ok(resp) if resp.status().is_server_error() => (
+                format!("HTTP {}", resp.status()),
+                format!("Xeno returned HTTP {}", resp.status()),
+            ),
+            Err(e) if e.is_connect() => (e.to_string(), request_error(state, url, e)),
+            result => return result.map_err(|e| request_error(state, url, e)),
+        };
+        if attempt >= state.args.xeno_retries {
+            return Err(err);
+        }
+        attempt += 1;
+        log_retry(state, url, attempt, &reason, delay);
+        tokio::time::sleep(delay).await;
+        delay *= 2;
+    }
+}
+
+fn log_retry(state: &AppState, url: &str, attempt: u32, reason: &str, delay: Duration) {
+    store_entry(state, &LogEntry {
+        id: uuid::Uuid::new_v4().to_string(),
+        timestamp: Local::now(),
+        level: "warn".to_string(),
+        message: format!(
+            "Retrying Xeno request {} ({}/{}) in {}ms: {}",
+            url, attempt, state.args.xeno_retries, delay.as_millis(), reason
+        ),
+        source: Some("xeno".to_string()),
+        pid: None,
+        username: None,
+        tags: vec!["xeno".to_string(), "retry".to_string()],
+        repeat_count: 1,
+        fields: HashMap::new(),
+        data: None,
+    });
+}
+
+async fn fetch_clients_uncached(state: &AppState) -> Result<Vec<XenoClient>, String> {
+    let url = format!("{}{}", state.args.xeno_url, state.args.xeno_clients_path);
+    let resp = get_clients_with_retry(state, &url).await?;
 
     if !resp.status().is_success() {
         return Err(format!("Xeno returned HTTP {}", resp.status()));
     }
 
-    let raw: Vec<Vec<serde_json::Value>> = resp
+    let raw: serde_json::Value = resp
         .json()
         .await
-        .map_err(|e| format!("Failed to parse Xeno response: {}", e))?;
+        .map_err(|e| {
+            if e.is_timeout() {
+                request_error(state, &url, e)
+            } else {
+                format!("Failed to parse Xeno response: {}", e)
+            }
+        })?;
 
-    let logger_pids = state.logger_pids.read();
+    let rows = raw.as_array().ok_or_else(|| {
+        format!("Unexpected Xeno response: expected an array of clients, got {}", json_kind(&raw))
+    })?;
+    if let Some(bad) = rows.iter().find(|r| !r.is_array() && !r.is_object()) {
+        return Err(format!(
+            "Unexpected Xeno response: client rows must be arrays or objects, got {}",
+            json_kind(bad)
+        ));
+    }
+
+    Ok(rows.iter().filter_map(parse_client_row).collect())
+}
 
-    let clients = raw
-        .into_iter()
-        .filter_map(|row| {
+/// One client row. Xeno sends positional arrays `[pid, username, player_name,
+/// status, user_id?]`; objects with those keys are accepted too so field order
+/// or extra fields in newer builds don't drop clients.
+fn parse_client_row(row: &serde_json::Value) -> Option<XenoClient> {
+    let (pid, username, player_name, status, user_id) = match row {
+        serde_json::Value::Array(row) => {
             if row.len() < 4 {
                 return None;
             }
-            let pid = row[0].as_u64()?;
-            let username = row[1].as_str()?.to_string();
-            let player_name = row[2].as_str()?.to_string();
-            let status = row[3].as_u64()? as u8;
-            let user_id = row.get(4).and_then(|v| v.as_u64());
-            Some(XenoClient {
-                pid,
-                username,
-                player_name,
-                status,
-                status_text: status_text(status).to_string(),
-                user_id,
-                logger_attached: logger_pids.contains(&pid.to_string()),
-            })
-        })
-        .collect();
+            (
+                row[0].as_u64()?,
+                row[1].as_str()?,
+                row[2].as_str()?,
+                row[3].as_u64()?,
+                row.get(4).and_then(|v| v.as_u64()),
+            )
+        }
+        serde_json::Value::Object(obj) => (
+            obj.get("pid")?.as_u64()?,
+            obj.get("username")?.as_str()?,
+            obj.get("player_name").and_then(|v| v.as_str()).unwrap_or_default(),
+            obj.get("status")?.as_u64()?,
+            obj.get("user_id").and_then(|v| v.as_u64()),
+        ),
+        _ => return None,
+    };
+    let status = status as u8;
+    Some(XenoClient {
+        pid,
+        username: username.to_string(),
+        player_name: player_name.to_string(),
+        status,
+        status_text: status_text(status).to_string(),
+        user_id,
+        logger_attached: false,
+    })
+}
 
-    Ok(clients)
+fn json_kind(v: &serde_json::Value) -> &'static str {
+    match v {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "a boolean",
+        serde_json::Value::Number(_) => "a number",
+        serde_json::Value::String(_) => "a string",
+        serde_json::Value::Array(_) => "an array",
+        serde_json::Value::Object(_) => "an object",
+    }
 }
 
 pub async fn xeno_execute(
     state: &AppState,
-    script: &str,
+    script: impl AsRef<[u8]>,
     pids: &[String],
-) -> Result<(), String> {
-    let url = format!("{}/o", state.args.xeno_url);
+) -> Result<XenoExecuteOutcome, String> {
+    let url = format!("{}{}", state.args.xeno_url, state.args.xeno_execute_path);
     let clients_header = serde_json::to_string(pids).unwrap_or_else(|_| "[]".to_string());
 
-    let resp = state
+    let req = state
         .http_client
         .post(&url)
         .header("Content-Type", "text/plain")
         .header("Clients", &clients_header)
-        .body(script.to_string())
-        .send()
+        .body(script.as_ref().to_vec());
+    let started = Instant::now();
+    let resp = send_with_retry(state, &url, req)
         .await
-        .map_err(|e| format!("Cannot reach Xeno at {}: {}", url, e))?;
+        .map_err(|e| request_error(state, &url, e))?;
 
     if resp.status().is_success() {
-        Ok(())
+        let status = resp.status().as_u16();
+        let body = resp.text().await.unwrap_or_default();
+        Ok(XenoExecuteOutcome {
+            status,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+            body,
+        })
     } else {
         let status = resp.status();
         let body = resp.text().await.unwrap_or_default();
         Err(format!("Xeno returned HTTP {} — {}", status, body))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Serve `hits` requests on `listener`, answering each with `status`.
+    fn serve(listener: std::net::TcpListener, status: &'static str, hits: Arc<AtomicUsize>) {
+        listener.set_nonblocking(true).unwrap();
+        let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut sock, _)) = listener.accept().await {
+                hits.fetch_add(1, Ordering::SeqCst);
+                let mut buf = [0u8; 4096];
+                let _ = sock.read(&mut buf).await;
+                let resp = format!("HTTP/1.1 {}\r\ncontent-length: 2\r\nconnection: close\r\n\r\nok", status);
+                let _ = sock.write_all(resp.as_bytes()).await;
+            }
+        });
+    }
+
+    fn free_port() -> u16 {
+        std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+    }
+
+    #[tokio::test]
+    async fn retries_refused_connections_with_backoff() {
+        let port = free_port();
+        let hits = Arc::new(AtomicUsize::new(0));
+        let mut retries = Vec::new();
+        let started = Instant::now();
+
+        // Nothing listens for the first two attempts; the server comes up
+        // during the second backoff so the third attempt succeeds.
+        let resp = send_with_backoff(
+            reqwest::Client::new().get(format!("http://127.0.0.1:{}/o", port)),
+            3,
+            |attempt, delay, err| {
+                assert!(err.is_connect());
+                retries.push((attempt, delay));
+                if attempt == 2 {
+                    let listener = std::net::TcpListener::bind(("127.0.0.1", port)).unwrap();
+                    serve(listener, "200 OK", hits.clone());
+                }
+            },
+        )
+        .await
+        .expect("third attempt should succeed");
+
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.text().await.unwrap(), "ok");
+        assert_eq!(retries, vec![(1, Duration::from_millis(100)), (2, Duration::from_millis(200))]);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+        assert!(started.elapsed() >= Duration::from_millis(300));
+    }
+
+    #[tokio::test]
+    async fn http_errors_are_not_retried() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let hits = Arc::new(AtomicUsize::new(0));
+        serve(listener, "503 Service Unavailable", hits.clone());
+
+        let resp = send_with_backoff(
+            reqwest::Client::new().get(format!("http://127.0.0.1:{}/o", port)),
+            3,
+            |_, _, _| panic!("HTTP errors must not be retried"),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(resp.status(), 503);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn zero_retries_sends_once() {
+        let port = free_port();
+        let result = send_with_backoff(
+            reqwest::Client::new().get(format!("http://127.0.0.1:{}/o", port)),
+            0,
+            |_, _, _| panic!("no retries configured"),
+        )
+        .await;
+        assert!(result.unwrap_err().is_connect());
+    }
+}